@@ -11,13 +11,13 @@ async fn main() -> Result<()> {
     env_logger::init();
     let url = env::var("TURL")?;
     let dir = env::var("TDIR")?;
-    let mut client;
+    let client;
     if let (Ok(user), Ok(password)) = (env::var("TUSER"), env::var("TPWD")) {
         client = TransClient::with_auth(url.parse()?, BasicAuth { user, password });
     } else {
         client = TransClient::new(url.parse()?);
     }
-    let response: Result<RpcResponse<FreeSpace>> = client.free_space(dir).await;
+    let response: transmission_rpc::Result<RpcResponse<FreeSpace>> = client.free_space(dir).await;
     match response {
         Ok(_) => println!("Yay!"),
         Err(_) => panic!("Oh no!"),
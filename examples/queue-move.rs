@@ -8,7 +8,7 @@ async fn main() -> Result<()> {
     dotenv()?;
     env_logger::init();
     let url = env::var("TURL")?;
-    let mut client = if let (Ok(user), Ok(password)) = (env::var("TUSER"), env::var("TPWD")) {
+    let client = if let (Ok(user), Ok(password)) = (env::var("TUSER"), env::var("TPWD")) {
         TransClient::with_auth(url.parse()?, BasicAuth { user, password })
     } else {
         TransClient::new(url.parse()?)
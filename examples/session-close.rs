@@ -10,7 +10,7 @@ async fn main() -> Result<()> {
     dotenv().ok();
     env_logger::init();
     let url = env::var("TURL")?;
-    let mut client;
+    let client;
     if let (Ok(user), Ok(password)) = (env::var("TUSER"), env::var("TPWD")) {
         client = TransClient::with_auth(url.parse()?, BasicAuth { user, password });
     } else {
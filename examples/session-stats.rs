@@ -10,13 +10,14 @@ async fn main() -> Result<()> {
     dotenv().ok();
     env_logger::init();
     let url = env::var("TURL")?;
-    let mut client;
+    let client;
     if let (Ok(user), Ok(password)) = (env::var("TUSER"), env::var("TPWD")) {
         client = TransClient::with_auth(url.parse()?, BasicAuth { user, password });
     } else {
         client = TransClient::new(url.parse()?);
     }
-    let response: Result<RpcResponse<SessionStats>> = client.session_stats().await;
+    let response: transmission_rpc::Result<RpcResponse<SessionStats>> =
+        client.session_stats().await;
     match response {
         Ok(_) => println!("Yay!"),
         Err(_) => panic!("Oh no!"),
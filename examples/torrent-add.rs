@@ -12,19 +12,15 @@ async fn main() -> Result<()> {
     dotenv().ok();
     env_logger::init();
     let url = env::var("TURL")?;
-    let mut client;
+    let client;
     if let (Ok(user), Ok(password)) = (env::var("TUSER"), env::var("TPWD")) {
         client = TransClient::with_auth(url.parse()?, BasicAuth { user, password });
     } else {
         client = TransClient::new(url.parse()?);
     }
-    let add: TorrentAddArgs = TorrentAddArgs {
-        filename: Some(
-            "https://releases.ubuntu.com/20.04/ubuntu-20.04.2.0-desktop-amd64.iso.torrent"
-                .to_string(),
-        ),
-        ..TorrentAddArgs::default()
-    };
+    let add = TorrentAddArgs::from_url(
+        "https://releases.ubuntu.com/20.04/ubuntu-20.04.2.0-desktop-amd64.iso.torrent",
+    );
     let res: RpcResponse<TorrentAddedOrDuplicate> = client.torrent_add(add).await?;
     println!("Add result: {:?}", &res.is_ok());
     println!("response: {:?}", &res);
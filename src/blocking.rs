@@ -0,0 +1,422 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+//! A blocking client, for programs without an async runtime.
+//!
+//! [`TransClient`] has the methods of the async [`crate::TransClient`], with the same arguments
+//! and results, and drives them to completion on a runtime of its own:
+//!
+//! ```no_run
+//! use transmission_rpc::{blocking::TransClient, types::BasicAuth};
+//!
+//! # fn main() -> transmission_rpc::types::Result<()> {
+//! let client = TransClient::with_auth(
+//!     "http://localhost:9091/transmission/rpc".parse()?,
+//!     BasicAuth {
+//!         user: "transmission".to_string(),
+//!         password: "transmission".to_string(),
+//!     },
+//! );
+//! for torrent in client.torrent_get(None, None)?.arguments.torrents {
+//!     println!("{:?}", torrent.name);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The methods must not be called from within an async runtime, where they panic.
+
+use std::io;
+
+use futures_util::StreamExt;
+use reqwest::{Client, Url};
+use serde::de::DeserializeOwned;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::{
+    types::{
+        BandwidthGroups, BasicAuth, BlocklistUpdate, FreeSpace, GroupSetArgs, Id, Ids, Nothing,
+        PortTest, Protocol, RpcRequest, RpcResponse, RpcResponseArgument, SessionGet,
+        SessionGetField, SessionSetArgs, SessionStats, Torrent, TorrentAction, TorrentAddArgs,
+        TorrentAddedOrDuplicate, TorrentGetField, TorrentProjection, TorrentRenamePath,
+        TorrentSetArgs, Torrents,
+    },
+    watch::{TorrentEvent, WatchOptions},
+    Result, RetryPolicy, TransClientBuilder,
+};
+
+/// Blocking counterpart of [`crate::TransClient`], see the [module documentation](self).
+pub struct TransClient {
+    inner: crate::TransClient,
+    runtime: Runtime,
+}
+
+impl TransClient {
+    /// Returns a builder, finished with [`TransClientBuilder::build_blocking`].
+    pub fn builder(url: Url) -> TransClientBuilder {
+        TransClientBuilder::new(url)
+    }
+
+    /// Returns HTTP(S) client with configured Basic Auth
+    ///
+    /// # Panics
+    ///
+    /// If the runtime cannot be started
+    #[must_use]
+    pub fn with_auth(url: Url, basic_auth: BasicAuth) -> TransClient {
+        TransClient::from_async(crate::TransClient::with_auth(url, basic_auth))
+            .expect("failed to start the runtime of the blocking client")
+    }
+
+    /// Returns HTTP(S) client
+    ///
+    /// # Panics
+    ///
+    /// If the runtime cannot be started
+    #[must_use]
+    pub fn new(url: Url) -> TransClient {
+        TransClient::from_async(crate::TransClient::new(url))
+            .expect("failed to start the runtime of the blocking client")
+    }
+
+    /// # Panics
+    ///
+    /// If the runtime cannot be started
+    #[must_use]
+    pub fn new_with_client(url: Url, client: Client) -> TransClient {
+        TransClient::from_async(crate::TransClient::new_with_client(url, client))
+            .expect("failed to start the runtime of the blocking client")
+    }
+
+    pub(crate) fn from_async(inner: crate::TransClient) -> io::Result<TransClient> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(TransClient { inner, runtime })
+    }
+
+    pub fn set_auth(&mut self, basic_auth: BasicAuth) {
+        self.inner.set_auth(basic_auth);
+    }
+
+    /// See [`crate::TransClient::set_rpc_errors`].
+    pub fn set_rpc_errors(&mut self, enabled: bool) {
+        self.inner.set_rpc_errors(enabled);
+    }
+
+    /// See [`crate::TransClient::set_protocol`].
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.inner.set_protocol(protocol);
+    }
+
+    /// See [`crate::TransClient::set_retry_policy`].
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.inner.set_retry_policy(retry_policy);
+    }
+
+    /// Watches torrents for changes, see [`crate::TransClient::watch`]
+    ///
+    /// The iterator never ends on its own: each call to `next` blocks until an event is
+    /// available. To stop watching, drop the iterator, e.g. by breaking out of the loop over it;
+    /// the client can be used again afterwards.
+    pub fn watch(&self, options: WatchOptions) -> impl Iterator<Item = Result<TorrentEvent>> + '_ {
+        let runtime = &self.runtime;
+        let mut events = Box::pin(self.inner.watch(options));
+        std::iter::from_fn(move || runtime.block_on(events.next()))
+    }
+}
+
+/// Defines blocking wrappers of the async methods of [`crate::TransClient`] with the same
+/// name, arguments and result. Generic parameters go in square brackets.
+macro_rules! blocking {
+    ($(
+        $(#[$meta:meta])*
+        fn $name:ident $([$($generics:tt)*])? ($($arg:ident: $type:ty),*) -> $result:ty;
+    )*) => {
+        impl TransClient {
+            $(
+                $(#[$meta])*
+                pub fn $name $(<$($generics)*>)? (&self, $($arg: $type),*) -> $result {
+                    self.runtime.block_on(self.inner.$name($($arg),*))
+                }
+            )*
+        }
+
+        /// Names of the wrapped methods.
+        #[cfg(test)]
+        const WRAPPED: &[&str] = &[$(stringify!($name)),*];
+    };
+}
+
+blocking! {
+    /// Performs a session set call
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn session_set(args: SessionSetArgs) -> Result<RpcResponse<Nothing>>;
+
+    /// Performs a session get call
+    /// fields - if None then ALL fields
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn session_get(fields: Option<Vec<SessionGetField>>) -> Result<RpcResponse<SessionGet>>;
+
+    /// Performs a session stats call
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn session_stats() -> Result<RpcResponse<SessionStats>>;
+
+    /// Performs a session close call
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn session_close() -> Result<RpcResponse<Nothing>>;
+
+    /// Performs a blocklist update call
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn blocklist_update() -> Result<RpcResponse<BlocklistUpdate>>;
+
+    /// Performs a free space call
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn free_space(path: String) -> Result<RpcResponse<FreeSpace>>;
+
+    /// Performs a port test call
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn port_test() -> Result<RpcResponse<PortTest>>;
+
+    /// Move torrents with IDs specified in `ids` to the top of the download queue.
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn queue_move_top(ids: impl Into<Ids>) -> Result<RpcResponse<Nothing>>;
+
+    /// Move torrents with IDs specified in `ids` up in the download queue.
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn queue_move_up(ids: impl Into<Ids>) -> Result<RpcResponse<Nothing>>;
+
+    /// Move torrents with IDs specified in `ids` down in the download queue.
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn queue_move_down(ids: impl Into<Ids>) -> Result<RpcResponse<Nothing>>;
+
+    /// Move torrents with IDs specified in `ids` to the bottom of the download queue.
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn queue_move_bottom(ids: impl Into<Ids>) -> Result<RpcResponse<Nothing>>;
+
+    /// Performs a torrent get call
+    /// fields - if None then ALL fields
+    /// ids - if None then All items
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn torrent_get(
+        fields: Option<Vec<TorrentGetField>>,
+        ids: impl Into<Ids>
+    ) -> Result<RpcResponse<Torrents<Torrent>>>;
+
+    /// Performs a torrent get call deserializing into a [`TorrentProjection`], see
+    /// [`crate::TransClient::torrent_get_as`]
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error, or
+    /// [`Error::InvalidArguments`](crate::Error::InvalidArguments) if `T` requests no field
+    fn torrent_get_as[T: TorrentProjection](ids: impl Into<Ids>) -> Result<RpcResponse<Torrents<T>>>;
+
+    /// Performs a torrent get call using the table format, see
+    /// [`crate::TransClient::torrent_get_table`]
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn torrent_get_table(
+        fields: Option<Vec<TorrentGetField>>,
+        ids: impl Into<Ids>
+    ) -> Result<RpcResponse<Torrents<Torrent>>>;
+
+    /// Performs a torrent set call
+    /// args - the fields to update
+    /// ids - if None then All items
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn torrent_set(args: TorrentSetArgs, ids: impl Into<Ids>) -> Result<RpcResponse<Nothing>>;
+
+    /// Performs a torrent action call
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn torrent_action(action: TorrentAction, ids: impl Into<Ids>) -> Result<RpcResponse<Nothing>>;
+
+    /// Performs a torrent remove call
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn torrent_remove(ids: Vec<Id>, delete_local_data: bool) -> Result<RpcResponse<Nothing>>;
+
+    /// Performs a torrent set location call
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn torrent_set_location(
+        ids: Vec<Id>,
+        location: String,
+        move_from: Option<bool>
+    ) -> Result<RpcResponse<Nothing>>;
+
+    /// Performs a torrent rename path call
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn torrent_rename_path(
+        ids: Vec<Id>,
+        path: String,
+        name: String
+    ) -> Result<RpcResponse<TorrentRenamePath>>;
+
+    /// Performs a torrent add call
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error, or
+    /// [`Error::InvalidArguments`](crate::Error::InvalidArguments) if neither `filename` nor
+    /// `metainfo` is set
+    fn torrent_add(add: TorrentAddArgs) -> Result<RpcResponse<TorrentAddedOrDuplicate>>;
+
+    /// See [`TransClient::ensure_torrent`](crate::TransClient::ensure_torrent).
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error, [`Error::Rpc`](crate::Error::Rpc) if the daemon
+    /// reported a failure, or [`Error::UnexpectedResponse`](crate::Error::UnexpectedResponse)
+    /// if it neither added the torrent nor reported a duplicate
+    fn ensure_torrent(
+        add: TorrentAddArgs,
+        settings: Option<TorrentSetArgs>
+    ) -> Result<TorrentAddedOrDuplicate>;
+
+    /// Performs a group get call
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn group_get(group: Option<Vec<String>>) -> Result<RpcResponse<BandwidthGroups>>;
+
+    /// Performs a group set call
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    fn group_set(args: GroupSetArgs) -> Result<RpcResponse<Nothing>>;
+
+    /// Performs several calls, see [`crate::TransClient::batch`]
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidArguments`](crate::Error::InvalidArguments) if a request is a torrent
+    /// add without `filename` nor `metainfo`. With [`Protocol::JsonRpc`], also if two requests
+    /// have the same tag, and any IO error or HTTP failure of the batch
+    fn batch[RS: RpcResponseArgument + DeserializeOwned + std::fmt::Debug](
+        requests: Vec<RpcRequest>,
+        max_concurrency: usize
+    ) -> Result<Vec<Result<RpcResponse<RS>>>>;
+
+    /// Performs a JRPC call to the server, retrying according to `retry_policy` instead of the
+    /// client's [`RetryPolicy`]
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error, once `retry_policy` gives up, or
+    /// [`Error::InvalidArguments`](crate::Error::InvalidArguments) if `request` is a torrent
+    /// add without `filename` nor `metainfo`
+    fn call_with_retry[RS: RpcResponseArgument + DeserializeOwned + std::fmt::Debug](
+        request: RpcRequest,
+        retry_policy: &RetryPolicy
+    ) -> Result<RpcResponse<RS>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WRAPPED;
+
+    /// Fails when an async method of the client has no blocking wrapper.
+    #[test]
+    fn every_async_method_is_wrapped() {
+        let source = include_str!("lib.rs");
+        let source = &source[..source
+            .find("#[cfg(test)]\nmod tests")
+            .unwrap_or(source.len())];
+        let missing: Vec<_> = source
+            .split("pub async fn ")
+            .skip(1)
+            .filter_map(|rest| rest.split(['(', '<']).next())
+            .filter(|name| !WRAPPED.contains(name))
+            .collect();
+        assert!(missing.is_empty(), "no blocking wrapper for {missing:?}");
+    }
+
+    #[cfg(feature = "mock")]
+    #[test]
+    fn blocking_calls() -> crate::types::Result<()> {
+        use tokio::runtime::Runtime;
+
+        use super::TransClient;
+        use crate::{
+            mock::MockServer,
+            types::{Id, TorrentAddArgs, TorrentAddedOrDuplicate},
+        };
+
+        let server_runtime = Runtime::new()?;
+        let server = server_runtime.block_on(MockServer::start())?;
+
+        let client = TransClient::new(server.url());
+        let added = client
+            .torrent_add(TorrentAddArgs {
+                filename: Some(
+                    "magnet:?xt=urn:btih:e08c426aab2cc58649ae5e73690e3747117b3470".into(),
+                ),
+                ..TorrentAddArgs::default()
+            })?
+            .arguments;
+        let TorrentAddedOrDuplicate::TorrentAdded(torrent) = added else {
+            panic!("torrent not added: {added:?}");
+        };
+        server.renew_session_id();
+
+        let torrents = client
+            .torrent_get(None, vec![Id::Id(torrent.id.unwrap())])?
+            .arguments
+            .torrents;
+        assert_eq!(torrents.len(), 1);
+        assert_eq!(server.request_count(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+use std::{sync::Arc, time::Duration};
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Certificate, Client, ClientBuilder, Proxy, Url,
+};
+
+#[cfg(feature = "sync")]
+use crate::SharableTransClient;
+use crate::{
+    cassette::{Recorder, Recording, Replayer},
+    transport::{self, ReqwestTransport, Transport},
+    types::{BasicAuth, Protocol},
+    Result, RetryPolicy, TransClient,
+};
+
+/// Builder for [`TransClient`] and, with the `sync` and `blocking` features, `SharableTransClient`
+/// and `blocking::TransClient`.
+///
+/// Unless a ready-made [`Client`] is supplied with [`client`](Self::client), the HTTP client
+/// is configured from the TLS, proxy, header and timeout options of this builder. These options
+/// do not apply to `unix:` URLs, which are served by a `UnixTransport` on Unix platforms.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use reqwest::header::{HeaderName, HeaderValue};
+/// use transmission_rpc::{types::BasicAuth, TransClientBuilder};
+///
+/// # fn main() -> transmission_rpc::Result<()> {
+/// let url = "https://seedbox.example.com/transmission/rpc".parse().expect("valid URL");
+/// let client = TransClientBuilder::new(url)
+///     .basic_auth(BasicAuth {
+///         user: "transmission".to_string(),
+///         password: "transmission".to_string(),
+///     })
+///     .danger_accept_invalid_certs(true)
+///     .header(
+///         HeaderName::from_static("x-proxy-token"),
+///         HeaderValue::from_static("secret"),
+///     )
+///     .connect_timeout(Duration::from_secs(5))
+///     .timeout(Duration::from_secs(30))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[must_use]
+pub struct TransClientBuilder {
+    url: Url,
+    auth: Option<BasicAuth>,
+    session_id: Option<String>,
+    client: Option<Client>,
+    transport: Option<Arc<dyn Transport>>,
+    root_certificates: Vec<Certificate>,
+    accept_invalid_certs: bool,
+    proxies: Vec<Proxy>,
+    headers: HeaderMap,
+    user_agent: Option<HeaderValue>,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    rpc_errors: bool,
+    protocol: Protocol,
+    retry_policy: RetryPolicy,
+    recorder: Option<Recorder>,
+}
+
+impl TransClientBuilder {
+    /// Starts building a client for the RPC endpoint at `url`.
+    pub fn new(url: Url) -> Self {
+        TransClientBuilder {
+            url,
+            auth: None,
+            session_id: None,
+            client: None,
+            transport: None,
+            root_certificates: Vec::new(),
+            accept_invalid_certs: false,
+            proxies: Vec::new(),
+            headers: HeaderMap::new(),
+            user_agent: None,
+            connect_timeout: None,
+            timeout: None,
+            rpc_errors: false,
+            protocol: Protocol::default(),
+            retry_policy: RetryPolicy::default(),
+            recorder: None,
+        }
+    }
+
+    pub fn basic_auth(mut self, basic_auth: BasicAuth) -> Self {
+        self.auth = Some(basic_auth);
+        self
+    }
+
+    /// Uses `client` as-is instead of building one. TLS, proxy, header, user agent and
+    /// timeout options of this builder are ignored, and so is `client` if a
+    /// [`transport`](Self::transport) is set.
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Sends requests through `transport` instead of an HTTP client. TLS, proxy, header, user
+    /// agent and timeout options of this builder are ignored.
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Trusts `certificate` in addition to the system roots, e.g. the CA of a self-signed
+    /// seedbox.
+    pub fn add_root_certificate(mut self, certificate: Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Trusts the PEM-encoded CA certificate(s) in `pem`.
+    ///
+    /// # Errors
+    ///
+    /// If `pem` is not a valid PEM certificate bundle
+    pub fn add_root_certificate_pem(mut self, pem: &[u8]) -> Result<Self> {
+        self.root_certificates
+            .extend(Certificate::from_pem_bundle(pem)?);
+        Ok(self)
+    }
+
+    /// Disables TLS certificate validation.
+    ///
+    /// **WARNING:** this makes the connection, and the Basic Auth credentials sent over it,
+    /// vulnerable to interception. Prefer [`add_root_certificate`](Self::add_root_certificate).
+    pub fn danger_accept_invalid_certs(mut self, accept_invalid_certs: bool) -> Self {
+        self.accept_invalid_certs = accept_invalid_certs;
+        self
+    }
+
+    /// Routes requests through `proxy`. SOCKS proxies require the `socks` feature.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Sends `headers` with every request, e.g. tokens expected by a reverse proxy.
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Sends the header `name: value` with every request.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Sets the `User-Agent` header.
+    pub fn user_agent(mut self, user_agent: HeaderValue) -> Self {
+        self.user_agent = Some(user_agent);
+        self
+    }
+
+    /// Timeout for establishing the connection to the daemon.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Timeout for a whole request, from connecting until the response body has been read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Starts with a known `X-Transmission-Session-Id`, saving the initial 409 round trip.
+    pub fn session_id(mut self, session_id: String) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    /// See [`TransClient::set_rpc_errors`].
+    pub fn rpc_errors(mut self, enabled: bool) -> Self {
+        self.rpc_errors = enabled;
+        self
+    }
+
+    /// See [`TransClient::set_protocol`].
+    pub fn protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// See [`TransClient::set_retry_policy`].
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Records every request/response pair to `recorder`, see [`cassette`](crate::cassette).
+    pub fn record(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Answers every request from `replayer` instead of contacting the daemon, see
+    /// [`cassette`](crate::cassette). Shorthand for [`transport`](Self::transport).
+    pub fn replay(self, replayer: Replayer) -> Self {
+        self.transport(replayer)
+    }
+
+    /// Builds a [`TransClient`].
+    ///
+    /// # Errors
+    ///
+    /// If the HTTP client cannot be built, e.g. because the TLS backend cannot be initialized
+    pub fn build(self) -> Result<TransClient> {
+        Ok(TransClient::from_parts(self.into_parts()?))
+    }
+
+    /// Builds a [`SharableTransClient`].
+    ///
+    /// # Errors
+    ///
+    /// If the HTTP client cannot be built, e.g. because the TLS backend cannot be initialized
+    #[cfg(feature = "sync")]
+    pub fn build_sharable(self) -> Result<SharableTransClient> {
+        Ok(SharableTransClient::from_parts(self.into_parts()?))
+    }
+
+    /// Builds a [`blocking::TransClient`](crate::blocking::TransClient).
+    ///
+    /// # Errors
+    ///
+    /// If the HTTP client or the runtime of the blocking client cannot be built
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(self) -> Result<crate::blocking::TransClient> {
+        Ok(crate::blocking::TransClient::from_async(self.build()?)?)
+    }
+
+    fn into_parts(self) -> Result<ClientParts> {
+        let transport: Arc<dyn Transport> = match (self.transport, self.client) {
+            (Some(transport), _) => transport,
+            (None, Some(client)) => Arc::new(ReqwestTransport::new(client)),
+            (None, None) => match transport::unix(&self.url) {
+                Some(transport) => transport,
+                None => {
+                    let mut builder = ClientBuilder::new()
+                        .default_headers(self.headers)
+                        .danger_accept_invalid_certs(self.accept_invalid_certs);
+                    for certificate in self.root_certificates {
+                        builder = builder.add_root_certificate(certificate);
+                    }
+                    for proxy in self.proxies {
+                        builder = builder.proxy(proxy);
+                    }
+                    if let Some(user_agent) = self.user_agent {
+                        builder = builder.user_agent(user_agent);
+                    }
+                    if let Some(timeout) = self.connect_timeout {
+                        builder = builder.connect_timeout(timeout);
+                    }
+                    if let Some(timeout) = self.timeout {
+                        builder = builder.timeout(timeout);
+                    }
+                    Arc::new(ReqwestTransport::new(builder.build()?))
+                }
+            },
+        };
+        let transport = match self.recorder {
+            Some(recorder) => Arc::new(Recording {
+                inner: transport,
+                recorder,
+            }),
+            None => transport,
+        };
+        Ok(ClientParts {
+            url: self.url,
+            auth: self.auth,
+            session_id: self.session_id,
+            transport,
+            rpc_errors: self.rpc_errors,
+            protocol: self.protocol,
+            retry_policy: self.retry_policy,
+        })
+    }
+}
+
+/// Configuration shared by every client flavour, produced by [`TransClientBuilder`].
+pub(crate) struct ClientParts {
+    pub url: Url,
+    pub auth: Option<BasicAuth>,
+    pub session_id: Option<String>,
+    pub transport: Arc<dyn Transport>,
+    pub rpc_errors: bool,
+    pub protocol: Protocol,
+    pub retry_policy: RetryPolicy,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TransClientBuilder;
+    use crate::{
+        session::SessionStorage,
+        types::{BasicAuth, Result},
+    };
+
+    #[tokio::test]
+    async fn build_keeps_auth_and_session_id() -> Result<()> {
+        let client = TransClientBuilder::new("http://localhost:9091/transmission/rpc".parse()?)
+            .basic_auth(BasicAuth {
+                user: "user".to_string(),
+                password: "password".to_string(),
+            })
+            .session_id("abc".to_string())
+            .client(reqwest::Client::new())
+            .build()?;
+        assert_eq!(client.auth.map(|auth| auth.user), Some("user".to_string()));
+        let (session_id, _) = client.session_id.session().current().await;
+        assert_eq!(session_id.as_deref(), Some("abc"));
+        Ok(())
+    }
+}
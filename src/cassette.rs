@@ -0,0 +1,406 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+//! Recording and replaying of RPC traffic.
+//!
+//! A [`Recorder`] writes every request/response pair exchanged with the daemon to a
+//! JSON-lines cassette; a [`Replayer`] serves the pairs of a cassette back, in order, without
+//! any network access. This makes it possible to capture what a given daemon version answered
+//! once and reproduce it in tests:
+//!
+//! ```no_run
+//! use transmission_rpc::{
+//!     cassette::{Recorder, Replayer},
+//!     TransClientBuilder,
+//! };
+//!
+//! # #[tokio::main]
+//! # async fn main() -> transmission_rpc::Result<()> {
+//! let url: reqwest::Url = "http://localhost:9091/transmission/rpc"
+//!     .parse()
+//!     .expect("valid URL");
+//!
+//! // Against the real daemon
+//! let client = TransClientBuilder::new(url.clone())
+//!     .record(Recorder::create("session.jsonl")?)
+//!     .build()?;
+//! client.torrent_get(None, None).await?;
+//!
+//! // Later, in a test
+//! let client = TransClientBuilder::new(url)
+//!     .replay(Replayer::open("session.jsonl")?)
+//!     .build()?;
+//! client.torrent_get(None, None).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Each line of a cassette is a JSON object like
+//! `{"request":{"session_id":"…","body":{…}},"response":{"status":200,"headers":{…},"body":"…"}}`.
+//! The `Authorization` header is never recorded. Response bodies are kept verbatim so that
+//! parsing issues can be reproduced exactly: a body that is not valid UTF-8 is stored
+//! base64-encoded in `body_base64` instead of `body`.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs::File,
+    future,
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use base64::{engine::general_purpose::STANDARD as base64, Engine as _};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    transport::{HttpRequest, HttpResponse, Transport, TransportFuture},
+    Result,
+};
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Exchange {
+    request: RecordedRequest,
+    response: RecordedResponse,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RecordedRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    session_id: Option<String>,
+    body: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RecordedResponse {
+    status: u16,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_base64: Option<String>,
+}
+
+impl RecordedRequest {
+    fn method(&self) -> Option<&str> {
+        self.body.get("method").and_then(Value::as_str)
+    }
+}
+
+/// Writes the exchanges of a client to a cassette, see the [module documentation](self).
+pub struct Recorder {
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl Recorder {
+    /// Records to the file at `path`, truncating it.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be created
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Recorder> {
+        Ok(Recorder::to_writer(File::create(path)?))
+    }
+
+    /// Records to `out`. Every exchange is written and flushed as soon as it completes.
+    pub fn to_writer(out: impl Write + Send + 'static) -> Recorder {
+        Recorder {
+            out: Mutex::new(Box::new(out)),
+        }
+    }
+
+    fn record(&self, request: &HttpRequest, response: &HttpResponse) -> Result<()> {
+        let exchange = Exchange {
+            request: RecordedRequest {
+                session_id: request.session_id().map(String::from),
+                body: serde_json::from_slice(&request.body).unwrap_or_else(|_| {
+                    Value::String(String::from_utf8_lossy(&request.body).into())
+                }),
+            },
+            response: RecordedResponse {
+                status: response.status.as_u16(),
+                headers: response
+                    .headers
+                    .iter()
+                    .filter_map(|(name, value)| {
+                        Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+                    })
+                    .collect(),
+                body: String::from_utf8(response.body.clone()).unwrap_or_default(),
+                body_base64: std::str::from_utf8(&response.body)
+                    .is_err()
+                    .then(|| base64.encode(&response.body)),
+            },
+        };
+        let mut line = serde_json::to_vec(&exchange).expect("exchanges are serializable");
+        line.push(b'\n');
+        let mut out = self.out.lock().expect("recorder poisoned");
+        out.write_all(&line)?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Records the exchanges going through `inner` to `recorder`.
+pub(crate) struct Recording {
+    pub inner: Arc<dyn Transport>,
+    pub recorder: Recorder,
+}
+
+impl Transport for Recording {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let response = self.inner.send(request.clone()).await?;
+            self.recorder.record(&request, &response)?;
+            Ok(response)
+        })
+    }
+}
+
+/// Answers requests from a cassette, see the [module documentation](self).
+///
+/// A [`Transport`], usually installed with
+/// [`TransClientBuilder::replay`](crate::TransClientBuilder::replay). Exchanges are served in
+/// the order they were recorded. A request whose RPC method differs from the next recorded one,
+/// or a request made after the cassette ran out, fails with an [`Error::Io`](crate::Error::Io).
+#[derive(Debug)]
+pub struct Replayer {
+    exchanges: Mutex<VecDeque<Exchange>>,
+}
+
+impl Replayer {
+    /// Loads the cassette at `path`.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be read or is not a cassette
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Replayer> {
+        Replayer::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Loads a cassette from `reader`. Empty lines are skipped.
+    ///
+    /// # Errors
+    ///
+    /// If `reader` fails or a line is not a recorded exchange
+    pub fn from_reader(reader: impl BufRead) -> io::Result<Replayer> {
+        let mut exchanges = VecDeque::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            exchanges.push_back(serde_json::from_str(&line)?);
+        }
+        Ok(Replayer {
+            exchanges: Mutex::new(exchanges),
+        })
+    }
+
+    /// Number of exchanges not replayed yet.
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.exchanges.lock().expect("replayer poisoned").len()
+    }
+
+    fn replay(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        let mut exchanges = self.exchanges.lock().expect("replayer poisoned");
+        let exchange = exchanges
+            .pop_front()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "cassette exhausted"))?;
+        let method = serde_json::from_slice::<Value>(&request.body)
+            .ok()
+            .and_then(|body| body.get("method").and_then(Value::as_str).map(String::from));
+        if method.as_deref() != exchange.request.method() {
+            let message = format!(
+                "cassette expected {:?}, got {:?}",
+                exchange.request.method(),
+                method
+            );
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message).into());
+        }
+        let response = exchange.response;
+        let mut headers = HeaderMap::new();
+        for (name, value) in &response.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::try_from(name.as_str()),
+                HeaderValue::try_from(value.as_str()),
+            ) {
+                headers.insert(name, value);
+            }
+        }
+        let body = match response.body_base64 {
+            Some(encoded) => base64
+                .decode(encoded)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            None => response.body.into_bytes(),
+        };
+        Ok(HttpResponse {
+            status: StatusCode::from_u16(response.status)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            headers,
+            body,
+        })
+    }
+}
+
+impl Transport for Replayer {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(future::ready(self.replay(&request)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, Write},
+        sync::{Arc, Mutex},
+    };
+
+    use super::{Recorder, Replayer};
+    use crate::{
+        types::{Id, Result, TorrentGetField},
+        Error, TransClientBuilder,
+    };
+
+    const URL: &str = "http://localhost:9091/transmission/rpc";
+
+    /// Where a [`Recorder`] writes, readable once the recorder is gone.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    const CASSETTE: &str = r#"
+{"request":{"body":{"method":"torrent-get","arguments":{"fields":["id","wanted"],"ids":[1]}}},"response":{"status":409,"headers":{"x-transmission-session-id":"abc"},"body":""}}
+{"request":{"session_id":"abc","body":{"method":"torrent-get","arguments":{"fields":["id","wanted"],"ids":[1]}}},"response":{"status":200,"headers":{},"body":"{\"arguments\":{\"torrents\":[{\"id\":1,\"wanted\":[1,0]}]},\"result\":\"success\"}"}}
+"#;
+
+    #[tokio::test]
+    async fn replay() -> Result<()> {
+        let client = TransClientBuilder::new(URL.parse()?)
+            .replay(Replayer::from_reader(CASSETTE.as_bytes())?)
+            .build()?;
+        let torrents = client
+            .torrent_get(
+                Some(vec![TorrentGetField::Id, TorrentGetField::Wanted]),
+                vec![Id::Id(1)],
+            )
+            .await?
+            .arguments
+            .torrents;
+        assert_eq!(torrents[0].wanted, Some(vec![true, false]));
+
+        let error = client.session_stats().await.unwrap_err();
+        assert!(matches!(error, Error::Io(e) if e.kind() == io::ErrorKind::UnexpectedEof));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replay_rejects_other_methods() -> Result<()> {
+        let client = TransClientBuilder::new(URL.parse()?)
+            .replay(Replayer::from_reader(CASSETTE.as_bytes())?)
+            .build()?;
+        let error = client.session_stats().await.unwrap_err();
+        assert!(matches!(error, Error::Io(e) if e.kind() == io::ErrorKind::InvalidData));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn binary_bodies_are_replayed_verbatim() -> Result<()> {
+        use std::future;
+
+        use reqwest::{header::HeaderMap, StatusCode};
+
+        use super::Recording;
+        use crate::transport::{HttpRequest, HttpResponse, Transport, TransportFuture};
+
+        const BODY: &[u8] = b"\xff\xfe not UTF-8";
+
+        struct Binary;
+
+        impl Transport for Binary {
+            fn send(&self, _request: HttpRequest) -> TransportFuture<'_> {
+                Box::pin(future::ready(Ok(HttpResponse {
+                    status: StatusCode::BAD_GATEWAY,
+                    headers: HeaderMap::new(),
+                    body: BODY.to_vec(),
+                })))
+            }
+        }
+
+        let buffer = Buffer::default();
+        let request = HttpRequest::new(
+            URL.parse()?,
+            None,
+            None,
+            br#"{"method":"session-stats"}"#.to_vec(),
+        );
+        let recording = Recording {
+            inner: Arc::new(Binary),
+            recorder: Recorder::to_writer(buffer.clone()),
+        };
+        recording.send(request.clone()).await?;
+
+        let cassette = buffer.0.lock().unwrap().clone();
+        let replayed = Replayer::from_reader(&cassette[..])?.send(request).await?;
+        assert_eq!(replayed.body, BODY);
+        Ok(())
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn record_then_replay() -> Result<()> {
+        use crate::{
+            mock::MockServer,
+            types::{BasicAuth, TorrentAddArgs},
+        };
+
+        let server = MockServer::start_with_auth(BasicAuth {
+            user: "user".into(),
+            password: "secret".into(),
+        })
+        .await?;
+        let buffer = Buffer::default();
+        let client = TransClientBuilder::new(server.url())
+            .basic_auth(BasicAuth {
+                user: "user".into(),
+                password: "secret".into(),
+            })
+            .record(Recorder::to_writer(buffer.clone()))
+            .build()?;
+        let add = TorrentAddArgs {
+            filename: Some("magnet:?xt=urn:btih:e08c426aab2cc58649ae5e73690e3747117b3470".into()),
+            ..TorrentAddArgs::default()
+        };
+        client.torrent_add(add.clone()).await?;
+        let recorded = client.torrent_get(None, None).await?.arguments.torrents;
+        drop(server);
+
+        let cassette = buffer.0.lock().unwrap().clone();
+        assert!(!String::from_utf8_lossy(&cassette).contains("Basic "));
+        let replayer = Replayer::from_reader(&cassette[..])?;
+        assert_eq!(replayer.remaining(), 3);
+        let client = TransClientBuilder::new(URL.parse()?)
+            .replay(replayer)
+            .build()?;
+        client.torrent_add(add).await?;
+        let replayed = client.torrent_get(None, None).await?.arguments.torrents;
+        assert_eq!(replayed[0].hash_string, recorded[0].hash_string);
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+use reqwest::StatusCode;
+
+/// Result type returned by the client methods.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors returned by [`TransClient`](crate::TransClient) calls.
+#[derive(Debug)]
+pub enum Error {
+    /// The HTTP client could not be built, the request could not be sent or its response could
+    /// not be read.
+    Transport(reqwest::Error),
+    /// An I/O error outside of the HTTP client, e.g. while reading or writing a
+    /// [cassette](crate::cassette).
+    Io(std::io::Error),
+    /// The daemon rejected the credentials (HTTP 401).
+    Unauthorized,
+    /// The daemon refused the request, usually because of `rpc-whitelist` or
+    /// `rpc-host-whitelist` (HTTP 403).
+    Forbidden,
+    /// The daemon answered with a HTTP status the client does not know how to handle.
+    HttpStatus { status: StatusCode, body: String },
+    /// The daemon answered 409 without a usable `X-Transmission-Session-Id` header.
+    NoSessionIdReceived,
+    /// The daemon kept answering 409 after the session id was renewed.
+    MaxRetriesReached,
+    /// The response body is not the expected JSON.
+    Decode {
+        source: serde_json::Error,
+        body: String,
+    },
+    /// The daemon processed the request but reported a failure.
+    ///
+    /// With [`Protocol::Legacy`] this is only returned when the client is configured to do so
+    /// with [`TransClient::set_rpc_errors`](crate::TransClient::set_rpc_errors), `result` then
+    /// holds the daemon's `result` string. With [`Protocol::JsonRpc`] the daemon's `error`
+    /// object is always returned this way, along with its `code`.
+    ///
+    /// [`Protocol::Legacy`]: crate::types::Protocol::Legacy
+    /// [`Protocol::JsonRpc`]: crate::types::Protocol::JsonRpc
+    Rpc {
+        method: &'static str,
+        tag: Option<u64>,
+        code: Option<i64>,
+        result: String,
+    },
+    /// The request was not sent because its arguments are invalid, e.g. a `torrent-add` with
+    /// neither `filename` nor `metainfo`.
+    InvalidArguments {
+        method: &'static str,
+        reason: &'static str,
+    },
+    /// The daemon reported success, but its answer does not make sense to the client, e.g. a
+    /// torrent it just added cannot be found.
+    UnexpectedResponse {
+        method: &'static str,
+        reason: String,
+    },
+    /// The response carries the `tag` of another request, e.g. because of a misbehaving proxy.
+    TagMismatch {
+        method: &'static str,
+        sent: u64,
+        received: u64,
+    },
+}
+
+impl Error {
+    /// Returns the HTTP status associated with this error, if any.
+    #[must_use]
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::Transport(e) => e.status(),
+            Error::Unauthorized => Some(StatusCode::UNAUTHORIZED),
+            Error::Forbidden => Some(StatusCode::FORBIDDEN),
+            Error::HttpStatus { status, .. } => Some(*status),
+            Error::NoSessionIdReceived | Error::MaxRetriesReached => Some(StatusCode::CONFLICT),
+            Error::Io(_)
+            | Error::Decode { .. }
+            | Error::Rpc { .. }
+            | Error::InvalidArguments { .. }
+            | Error::UnexpectedResponse { .. }
+            | Error::TagMismatch { .. } => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Transport(e) => write!(f, "Transport error: {e}"),
+            Error::Io(e) => write!(f, "I/O error: {e}"),
+            Error::Unauthorized => write!(f, "Unauthorized!"),
+            Error::Forbidden => write!(f, "Forbidden!"),
+            Error::HttpStatus { status, .. } => write!(f, "Unexpected HTTP status: {status}"),
+            Error::NoSessionIdReceived => write!(f, "No session id received!"),
+            Error::MaxRetriesReached => write!(f, "Max retries reached!"),
+            Error::Decode { source, .. } => write!(f, "Unable to decode response: {source}"),
+            Error::Rpc {
+                method,
+                tag: Some(tag),
+                result,
+                ..
+            } => write!(f, "{method} (tag {tag}) failed: {result}"),
+            Error::Rpc { method, result, .. } => write!(f, "{method} failed: {result}"),
+            Error::InvalidArguments { method, reason } => {
+                write!(f, "Invalid {method} arguments: {reason}")
+            }
+            Error::UnexpectedResponse { method, reason } => {
+                write!(f, "Unexpected {method} response: {reason}")
+            }
+            Error::TagMismatch {
+                method,
+                sent,
+                received,
+            } => write!(
+                f,
+                "{method} sent with tag {sent} was answered with tag {received}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Transport(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Decode { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Transport(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
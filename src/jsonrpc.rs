@@ -0,0 +1,363 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+//! Translation between the legacy Transmission RPC protocol and JSON-RPC 2.0.
+//!
+//! Transmission 4.1+ speaks JSON-RPC 2.0 with `snake_case` method names and keys. The request
+//! and response types of this crate keep modelling the legacy protocol; this module rewrites a
+//! serialized [`RpcRequest`] into a JSON-RPC request and a JSON-RPC response back into the
+//! legacy `{"arguments": ..., "result": ...}` shape so the same typed structs can be used.
+
+use serde_json::{json, Map, Value};
+
+use crate::types::RpcRequest;
+
+/// Legacy keys that do not follow the `kebab-case` convention of their method.
+const CAMEL_CASE_KEYS: &[&str] = &["seedRatioLimit", "seedRatioLimited", "honorsSessionLimits"];
+
+/// Legacy keys that do not follow the `camelCase` convention of their method.
+const KEBAB_CASE_KEYS: &[&str] = &[
+    "cumulative-stats",
+    "current-stats",
+    "file-count",
+    "peer-limit",
+    "primary-mime-type",
+    "torrent-added",
+    "torrent-duplicate",
+];
+
+/// Legacy keys whose casing is irregular.
+const IRREGULAR_KEYS: &[&str] = &["isUTP"];
+
+#[derive(Clone, Copy)]
+enum Case {
+    Camel,
+    Kebab,
+}
+
+impl Case {
+    /// Returns the casing used by the legacy protocol for the response of `method`.
+    fn of(method: &str) -> Case {
+        match method {
+            "blocklist-update" | "free-space" | "group-get" | "port-test" | "session-get" => {
+                Case::Kebab
+            }
+            _ => Case::Camel,
+        }
+    }
+
+    fn exceptions(self) -> &'static [&'static str] {
+        match self {
+            Case::Camel => KEBAB_CASE_KEYS,
+            Case::Kebab => CAMEL_CASE_KEYS,
+        }
+    }
+
+    fn apply(self, key: &str) -> String {
+        if let Some(exception) = self
+            .exceptions()
+            .iter()
+            .chain(IRREGULAR_KEYS)
+            .find(|exception| to_snake_case(exception) == key)
+        {
+            return exception.to_string();
+        }
+        match self {
+            Case::Camel => {
+                let mut parts = key.split('_');
+                let mut out = parts.next().unwrap_or_default().to_string();
+                for part in parts {
+                    let mut chars = part.chars();
+                    if let Some(first) = chars.next() {
+                        out.extend(first.to_uppercase());
+                        out.push_str(chars.as_str());
+                    }
+                }
+                out
+            }
+            Case::Kebab => key.replace('_', "-"),
+        }
+    }
+}
+
+/// Converts a legacy `camelCase` or `kebab-case` identifier to `snake_case`.
+pub(crate) fn to_snake_case(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 4);
+    let mut prev_lower = false;
+    for c in s.chars() {
+        if c == '-' {
+            out.push('_');
+            prev_lower = false;
+        } else if c.is_ascii_uppercase() {
+            if prev_lower {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+            prev_lower = false;
+        } else {
+            out.push(c);
+            prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        }
+    }
+    out
+}
+
+/// Recursively renames object keys to `snake_case`. Field names listed under a `fields` key are
+/// renamed as well since they refer to keys, and so is the `"recently-active"` ids selector.
+fn snake_case_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let value = match (key.as_str(), value) {
+                        ("fields", Value::Array(fields)) => Value::Array(
+                            fields
+                                .into_iter()
+                                .map(|field| match field {
+                                    Value::String(field) => Value::String(to_snake_case(&field)),
+                                    other => other,
+                                })
+                                .collect(),
+                        ),
+                        ("ids", Value::String(ids)) => Value::String(to_snake_case(&ids)),
+                        (_, value) => snake_case_keys(value),
+                    };
+                    (to_snake_case(&key), value)
+                })
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(snake_case_keys).collect()),
+        other => other,
+    }
+}
+
+/// Recursively renames `snake_case` object keys to their legacy spelling.
+fn legacy_keys(value: Value, case: Case) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (case.apply(&key), legacy_keys(value, case)))
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| legacy_keys(value, case))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Renames the header row of a `"table"` format torrent-get, whose field names are values
+/// rather than keys.
+fn legacy_table_header(mut arguments: Value, method: &str) -> Value {
+    if method != "torrent-get" {
+        return arguments;
+    }
+    if let Some(Value::Array(header)) = arguments.pointer_mut("/torrents/0") {
+        for field in header {
+            if let Value::String(name) = field {
+                *name = Case::Camel.apply(name);
+            }
+        }
+    }
+    arguments
+}
+
+/// Builds the JSON-RPC 2.0 request object for `request`.
+pub(crate) fn encode_request(request: &RpcRequest, id: u64) -> Value {
+    let legacy = serde_json::to_value(request).expect("RpcRequest is always serializable");
+    let mut out = Map::new();
+    out.insert("jsonrpc".into(), "2.0".into());
+    out.insert("method".into(), to_snake_case(request.method()).into());
+    if let Some(arguments) = legacy.get("arguments") {
+        out.insert("params".into(), snake_case_keys(arguments.clone()));
+    }
+    out.insert("id".into(), id.into());
+    Value::Object(out)
+}
+
+/// A JSON-RPC 2.0 `error` object.
+#[derive(Debug)]
+pub(crate) struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+/// Rewrites a JSON-RPC 2.0 response to `method` into the legacy response shape, or returns the
+/// `error` object sent by the daemon.
+pub(crate) fn decode_response(
+    method: &str,
+    response: Value,
+) -> std::result::Result<Value, RpcError> {
+    let Value::Object(mut response) = response else {
+        return Ok(response);
+    };
+    if let Some(error) = response.remove("error") {
+        let message = error
+            .pointer("/data/error_string")
+            .or_else(|| error.get("message"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let code = error
+            .get("code")
+            .and_then(Value::as_i64)
+            .unwrap_or_default();
+        return Err(RpcError { code, message });
+    }
+    let arguments = match response.remove("result") {
+        None | Some(Value::Null) => json!({}),
+        Some(result) => legacy_table_header(legacy_keys(result, Case::of(method)), method),
+    };
+    Ok(json!({
+        "arguments": arguments,
+        "result": "success",
+        "tag": response.remove("id"),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{decode_response, encode_request, to_snake_case};
+    use crate::types::{
+        Id, Result, RpcRequest, RpcResponse, SessionGet, Torrent, TorrentGetField, Torrents,
+    };
+
+    #[test]
+    fn snake_case() {
+        assert_eq!(to_snake_case("hashString"), "hash_string");
+        assert_eq!(to_snake_case("peer-limit"), "peer_limit");
+        assert_eq!(to_snake_case("isUTP"), "is_utp");
+        assert_eq!(
+            to_snake_case("torrent-set-location"),
+            "torrent_set_location"
+        );
+    }
+
+    #[test]
+    fn encode_torrent_get() {
+        let request = RpcRequest::torrent_get(
+            Some(vec![
+                TorrentGetField::HashString,
+                TorrentGetField::FileCount,
+            ]),
+            Some(vec![Id::Id(1)]),
+        );
+        assert_eq!(
+            encode_request(&request, 7),
+            json!({
+                "jsonrpc": "2.0",
+                "method": "torrent_get",
+                "params": {
+                    "fields": ["hash_string", "file_count"],
+                    "ids": [1]
+                },
+                "id": 7
+            })
+        );
+    }
+
+    #[test]
+    fn encode_without_params() {
+        assert_eq!(
+            encode_request(&RpcRequest::session_stats(), 1),
+            json!({ "jsonrpc": "2.0", "method": "session_stats", "id": 1 })
+        );
+    }
+
+    #[test]
+    fn decode_torrent_get() -> Result<()> {
+        let legacy = decode_response(
+            "torrent-get",
+            json!({
+                "jsonrpc": "2.0",
+                "result": {
+                    "torrents": [{
+                        "hash_string": "e08c426aab2cc58649ae5e73690e3747117b3470",
+                        "file_count": 2,
+                        "peer_limit": 50,
+                        "peers": [],
+                        "file_stats": [{ "bytes_completed": 0, "wanted": true, "priority": 0 }]
+                    }]
+                },
+                "id": 7
+            }),
+        )
+        .expect("success response");
+        let resp: RpcResponse<Torrents<Torrent>> = serde_json::from_value(legacy)?;
+        assert!(resp.is_ok());
+        assert_eq!(resp.tag, Some(7));
+        let torrent = &resp.arguments.torrents[0];
+        assert_eq!(
+            torrent.hash_string.as_deref(),
+            Some("e08c426aab2cc58649ae5e73690e3747117b3470")
+        );
+        assert_eq!(torrent.file_count, Some(2));
+        assert_eq!(torrent.peer_limit, Some(50));
+        assert_eq!(torrent.file_stats.as_ref().map(Vec::len), Some(1));
+        Ok(())
+    }
+
+    #[test]
+    fn decode_session_get() -> Result<()> {
+        let legacy = decode_response(
+            "session-get",
+            json!({
+                "jsonrpc": "2.0",
+                "result": {
+                    "rpc_version_semver": "6.0.0",
+                    "seed_ratio_limit": 2.0,
+                    "units": { "speed_bytes": 1000, "speed_units": [], "size_bytes": 1000,
+                               "size_units": [], "memory_bytes": 1024, "memory_units": [] }
+                },
+                "id": 1
+            }),
+        )
+        .expect("success response");
+        let resp: RpcResponse<SessionGet> = serde_json::from_value(legacy)?;
+        assert_eq!(resp.arguments.rpc_version_semver.as_deref(), Some("6.0.0"));
+        assert_eq!(resp.arguments.seed_ratio_limit, Some(2.0));
+        assert_eq!(resp.arguments.units.map(|u| u.memory_bytes), Some(1024));
+        Ok(())
+    }
+
+    #[test]
+    fn decode_torrent_get_table() {
+        let legacy = decode_response(
+            "torrent-get",
+            json!({
+                "jsonrpc": "2.0",
+                "result": { "torrents": [["id", "hash_string", "file_count"], [1, "abc", 2]] },
+                "id": 7
+            }),
+        )
+        .expect("success response");
+        assert_eq!(
+            legacy["arguments"]["torrents"],
+            json!([["id", "hashString", "file-count"], [1, "abc", 2]])
+        );
+    }
+
+    #[test]
+    fn decode_error() {
+        let error = decode_response(
+            "torrent-add",
+            json!({
+                "jsonrpc": "2.0",
+                "error": {
+                    "code": 7,
+                    "message": "HTTP error from backend service",
+                    "data": { "error_string": "Couldn't fetch torrent: Not Found (404)" }
+                },
+                "id": 3
+            }),
+        )
+        .expect_err("error response");
+        assert_eq!(error.code, 7);
+        assert_eq!(error.message, "Couldn't fetch torrent: Not Found (404)");
+    }
+}
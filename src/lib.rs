@@ -43,12 +43,17 @@
 //! - [ ] queue-move-top, queue-move-up, queue-move-down, queue-move-bottom
 //! - [X] session-close
 //! - [X] free-space
-//! - [ ] group-set
-//! - [ ] group-get
+//! - [X] group-set
+//! - [X] group-get
 //!
 //! ##### Feature Flags
 //!
-//! - `sync`: Enables a thread-safe version of `TransClient`.
+//! - `sync`: Enables `SharableTransClient`, a `TransClient` whose clones share their session id.
+//! - `blocking`: Enables `blocking::TransClient`, which does not need an async runtime.
+//! - `socks`: Enables SOCKS proxies in `TransClientBuilder::proxy`.
+//! - `mock`: Enables `mock::MockServer`, an in-process Transmission daemon for tests.
+//! - `tracing`: Runs every call in a `tracing` span with its method, tag, attempts and latency.
+//! - `regex`: Enables `selection::Pattern::regex`, to select files with regular expressions.
 //!
 //! -----
 //!
@@ -59,96 +64,171 @@
 #[macro_use]
 extern crate log;
 
-use reqwest::{header::CONTENT_TYPE, Client, StatusCode, Url};
+use std::{
+    collections::{HashMap, HashSet},
+    slice,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use futures_util::{stream, Stream, StreamExt};
+use reqwest::{Client, StatusCode, Url};
 use serde::de::DeserializeOwned;
+use serde_json::Value;
 
+use builder::ClientParts;
+pub use builder::TransClientBuilder;
+pub use error::{Error, Result};
+pub use retry::RetryPolicy;
+use session::{OwnedSessionId, Session, SessionStorage};
 #[cfg(feature = "sync")]
 pub use sync::SharableTransClient;
+use transport::{HttpRequest, HttpResponse, ReqwestTransport, Transport};
 use types::{
-    BasicAuth, BlocklistUpdate, FreeSpace, Id, Nothing, PortTest, Result, RpcRequest, RpcResponse,
-    RpcResponseArgument, SessionGet, SessionSetArgs, SessionStats, Torrent, TorrentAction,
-    TorrentAddArgs, TorrentAddedOrDuplicate, TorrentGetField, TorrentRenamePath, TorrentSetArgs,
-    Torrents,
+    BandwidthGroups, BasicAuth, BlocklistUpdate, FreeSpace, GroupSetArgs, Id, Ids, Nothing,
+    PortTest, Protocol, RpcRequest, RpcResponse, RpcResponseArgument, SessionGet, SessionGetField,
+    SessionSetArgs, SessionStats, Torrent, TorrentAction, TorrentAddArgs, TorrentAddedOrDuplicate,
+    TorrentGetField, TorrentProjection, TorrentRenamePath, TorrentSetArgs, TorrentTable, Torrents,
 };
+use watch::{TorrentEvent, WatchOptions, Watcher};
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod builder;
+mod error;
+mod jsonrpc;
+#[cfg(feature = "mock")]
+pub mod mock;
+mod retry;
 #[cfg(feature = "sync")]
 mod sync;
+mod trace;
 
+pub mod cassette;
+pub mod magnet;
+pub mod metainfo;
+pub mod selection;
+pub mod session;
+pub mod transport;
 pub mod types;
+pub mod watch;
 
 const MAX_RETRIES: usize = 5;
 
-#[derive(Clone, Debug)]
-pub enum TransError {
-    MaxRetriesReached,
-    NoSessionIdReceived,
+#[deprecated(note = "use `Error` instead")]
+pub type TransError = Error;
+
+/// Client of the Transmission RPC, generic over where it keeps the session id.
+///
+/// Use it through [`TransClient`] or, with the `sync` feature, `SharableTransClient`: both
+/// have the same methods and only differ by their [`SessionStorage`].
+pub struct RpcClient<S> {
+    url: Url,
+    auth: Option<BasicAuth>,
+    session_id: S,
+    transport: Arc<dyn Transport>,
+    rpc_errors: bool,
+    protocol: Protocol,
+    retry_policy: RetryPolicy,
+    request_id: Arc<AtomicU64>,
 }
 
-impl std::fmt::Display for TransError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match *self {
-            TransError::MaxRetriesReached => write!(f, "Max retries reached!"),
-            TransError::NoSessionIdReceived => write!(f, "No session id received!"),
+/// Client of the Transmission RPC.
+pub type TransClient = RpcClient<OwnedSessionId>;
+
+impl<S: SessionStorage + Clone> Clone for RpcClient<S> {
+    fn clone(&self) -> Self {
+        RpcClient {
+            url: self.url.clone(),
+            auth: self.auth.clone(),
+            session_id: self.session_id.clone(),
+            transport: self.transport.clone(),
+            rpc_errors: self.rpc_errors,
+            protocol: self.protocol,
+            retry_policy: self.retry_policy.clone(),
+            request_id: self.request_id.clone(),
         }
     }
 }
 
-impl std::error::Error for TransError {}
+impl<S: SessionStorage> RpcClient<S> {
+    /// Returns a [`TransClientBuilder`] to configure TLS, proxies, headers, timeouts and more
+    pub fn builder(url: Url) -> TransClientBuilder {
+        TransClientBuilder::new(url)
+    }
 
-pub struct TransClient {
-    url: Url,
-    auth: Option<BasicAuth>,
-    session_id: Option<String>,
-    client: Client,
-}
+    pub(crate) fn from_parts(parts: ClientParts) -> Self {
+        RpcClient {
+            url: parts.url,
+            auth: parts.auth,
+            session_id: S::from_session(Session::new(parts.session_id)),
+            transport: parts.transport,
+            rpc_errors: parts.rpc_errors,
+            protocol: parts.protocol,
+            retry_policy: parts.retry_policy,
+            request_id: Arc::default(),
+        }
+    }
 
-impl TransClient {
-    /// Returns HTTP(S) client with configured Basic Auth
-    #[must_use]
-    pub fn with_auth(url: Url, basic_auth: BasicAuth) -> TransClient {
-        TransClient {
+    fn with_transport(url: Url, transport: Arc<dyn Transport>) -> Self {
+        RpcClient {
             url,
-            auth: Some(basic_auth),
-            session_id: None,
-            client: Client::new(),
+            auth: None,
+            session_id: S::from_session(Session::default()),
+            transport,
+            rpc_errors: false,
+            protocol: Protocol::Legacy,
+            retry_policy: RetryPolicy::default(),
+            request_id: Arc::default(),
         }
     }
 
+    /// Returns HTTP(S) client with configured Basic Auth
+    #[must_use]
+    pub fn with_auth(url: Url, basic_auth: BasicAuth) -> Self {
+        let mut client = Self::new(url);
+        client.set_auth(basic_auth);
+        client
+    }
+
     /// Returns HTTP(S) client
     #[must_use]
-    pub fn new(url: Url) -> TransClient {
-        TransClient {
-            url,
-            auth: None,
-            session_id: None,
-            client: Client::new(),
-        }
+    pub fn new(url: Url) -> Self {
+        let transport = transport::for_url(&url);
+        Self::with_transport(url, transport)
     }
 
     #[must_use]
-    pub fn new_with_client(url: Url, client: Client) -> TransClient {
-        TransClient {
-            url,
-            auth: None,
-            session_id: None,
-            client,
-        }
+    pub fn new_with_client(url: Url, client: Client) -> Self {
+        Self::with_transport(url, Arc::new(ReqwestTransport::new(client)))
     }
 
     pub fn set_auth(&mut self, basic_auth: BasicAuth) {
         self.auth = Some(basic_auth);
     }
 
-    /// Prepares a request for provided server and auth
-    fn rpc_request(&self) -> reqwest::RequestBuilder {
-        if let Some(auth) = &self.auth {
-            self.client
-                .post(self.url.clone())
-                .basic_auth(&auth.user, Some(&auth.password))
-        } else {
-            self.client.post(self.url.clone())
-        }
-        .header(CONTENT_TYPE, "application/json")
+    /// When enabled, responses whose `result` is not `"success"` are returned as
+    /// [`Error::Rpc`] instead of `Ok`, so `?` propagates daemon-side failures.
+    ///
+    /// Disabled by default.
+    pub fn set_rpc_errors(&mut self, enabled: bool) {
+        self.rpc_errors = enabled;
+    }
+
+    /// Selects the RPC [`Protocol`] spoken by the daemon.
+    ///
+    /// Defaults to [`Protocol::Legacy`], which every Transmission version understands.
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.protocol = protocol;
+    }
+
+    /// Sets the [`RetryPolicy`] applied to every call of this client.
+    ///
+    /// Use [`call_with_retry`](Self::call_with_retry) to override it for a single call.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
     }
 
     /// Performs a session set call
@@ -177,7 +257,7 @@ impl TransClient {
     ///         user: env::var("TUSER")?,
     ///         password: env::var("TPWD")?,
     ///     };
-    ///     let mut client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
     ///     let args: SessionSetArgs = SessionSetArgs {
     ///         download_dir: Some(
     ///             "/torrent/download".to_string(),
@@ -193,11 +273,12 @@ impl TransClient {
     ///     Ok(())
     /// }
     /// ```
-    pub async fn session_set(&mut self, args: SessionSetArgs) -> Result<RpcResponse<Nothing>> {
+    pub async fn session_set(&self, args: SessionSetArgs) -> Result<RpcResponse<Nothing>> {
         self.call(RpcRequest::session_set(args)).await
     }
 
     /// Performs a session get call
+    /// fields - if None then ALL fields
     ///
     /// # Errors
     ///
@@ -225,8 +306,8 @@ impl TransClient {
     ///         user: env::var("TUSER")?,
     ///         password: env::var("TPWD")?,
     ///     };
-    ///     let mut client = TransClient::with_auth(url.parse()?, basic_auth);
-    ///     let response: Result<RpcResponse<SessionGet>> = client.session_get().await;
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let response: transmission_rpc::Result<RpcResponse<SessionGet>> = client.session_get(None).await;
     ///     match response {
     ///         Ok(_) => println!("Yay!"),
     ///         Err(_) => panic!("Oh no!"),
@@ -235,8 +316,11 @@ impl TransClient {
     ///     Ok(())
     /// }
     /// ```
-    pub async fn session_get(&mut self) -> Result<RpcResponse<SessionGet>> {
-        self.call(RpcRequest::session_get()).await
+    pub async fn session_get(
+        &self,
+        fields: Option<Vec<SessionGetField>>,
+    ) -> Result<RpcResponse<SessionGet>> {
+        self.call(RpcRequest::session_get(fields)).await
     }
 
     /// Performs a session stats call
@@ -267,8 +351,8 @@ impl TransClient {
     ///         user: env::var("TUSER")?,
     ///         password: env::var("TPWD")?,
     ///     };
-    ///     let mut client = TransClient::with_auth(url.parse()?, basic_auth);
-    ///     let response: Result<RpcResponse<SessionStats>> = client.session_stats().await;
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let response: transmission_rpc::Result<RpcResponse<SessionStats>> = client.session_stats().await;
     ///     match response {
     ///         Ok(_) => println!("Yay!"),
     ///         Err(_) => panic!("Oh no!"),
@@ -277,7 +361,7 @@ impl TransClient {
     ///     Ok(())
     /// }
     /// ```
-    pub async fn session_stats(&mut self) -> Result<RpcResponse<SessionStats>> {
+    pub async fn session_stats(&self) -> Result<RpcResponse<SessionStats>> {
         self.call(RpcRequest::session_stats()).await
     }
 
@@ -307,7 +391,7 @@ impl TransClient {
     ///         user: env::var("TUSER")?,
     ///         password: env::var("TPWD")?,
     ///     };
-    ///     let mut client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
     ///     let response = client.session_close().await;
     ///     match response {
     ///         Ok(_) => println!("Yay!"),
@@ -317,7 +401,7 @@ impl TransClient {
     ///     Ok(())
     /// }
     /// ```
-    pub async fn session_close(&mut self) -> Result<RpcResponse<Nothing>> {
+    pub async fn session_close(&self) -> Result<RpcResponse<Nothing>> {
         self.call(RpcRequest::session_close()).await
     }
 
@@ -349,8 +433,8 @@ impl TransClient {
     ///         user: env::var("TUSER")?,
     ///         password: env::var("TPWD")?,
     ///     };
-    ///     let mut client = TransClient::with_auth(url.parse()?, basic_auth);
-    ///     let response: Result<RpcResponse<BlocklistUpdate>> = client.blocklist_update().await;
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let response: transmission_rpc::Result<RpcResponse<BlocklistUpdate>> = client.blocklist_update().await;
     ///     match response {
     ///         Ok(_) => println!("Yay!"),
     ///         Err(_) => panic!("Oh no!"),
@@ -359,7 +443,7 @@ impl TransClient {
     ///     Ok(())
     /// }
     /// ```
-    pub async fn blocklist_update(&mut self) -> Result<RpcResponse<BlocklistUpdate>> {
+    pub async fn blocklist_update(&self) -> Result<RpcResponse<BlocklistUpdate>> {
         self.call(RpcRequest::blocklist_update()).await
     }
 
@@ -392,8 +476,8 @@ impl TransClient {
     ///         user: env::var("TUSER")?,
     ///         password: env::var("TPWD")?,
     ///     };
-    ///     let mut client = TransClient::with_auth(url.parse()?, basic_auth);
-    ///     let response: Result<RpcResponse<FreeSpace>> = client.free_space(dir).await;
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let response: transmission_rpc::Result<RpcResponse<FreeSpace>> = client.free_space(dir).await;
     ///     match response {
     ///         Ok(_) => println!("Yay!"),
     ///         Err(_) => panic!("Oh no!"),
@@ -402,7 +486,7 @@ impl TransClient {
     ///     Ok(())
     /// }
     /// ```
-    pub async fn free_space(&mut self, path: String) -> Result<RpcResponse<FreeSpace>> {
+    pub async fn free_space(&self, path: String) -> Result<RpcResponse<FreeSpace>> {
         self.call(RpcRequest::free_space(path)).await
     }
 
@@ -434,8 +518,8 @@ impl TransClient {
     ///         user: env::var("TUSER")?,
     ///         password: env::var("TPWD")?,
     ///     };
-    ///     let mut client = TransClient::with_auth(url.parse()?, basic_auth);
-    ///     let response: Result<RpcResponse<PortTest>> = client.port_test().await;
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let response: transmission_rpc::Result<RpcResponse<PortTest>> = client.port_test().await;
     ///     match response {
     ///         Ok(_) => println!("Yay!"),
     ///         Err(_) => panic!("Oh no!"),
@@ -444,7 +528,7 @@ impl TransClient {
     ///     Ok(())
     /// }
     /// ```
-    pub async fn port_test(&mut self) -> Result<RpcResponse<PortTest>> {
+    pub async fn port_test(&self) -> Result<RpcResponse<PortTest>> {
         self.call(RpcRequest::port_test()).await
     }
 
@@ -467,7 +551,7 @@ impl TransClient {
     ///     dotenv()?;
     ///     env_logger::init();
     ///     let url = env::var("TURL")?;
-    ///     let mut client = if let (Ok(user), Ok(password)) = (env::var("TUSER"), env::var("TPWD")) {
+    ///     let client = if let (Ok(user), Ok(password)) = (env::var("TUSER"), env::var("TPWD")) {
     ///         TransClient::with_auth(url.parse()?, BasicAuth { user, password })
     ///     } else {
     ///         TransClient::new(url.parse()?)
@@ -481,7 +565,7 @@ impl TransClient {
     ///     Ok(())
     /// }
     /// ```
-    pub async fn queue_move_top(&mut self, ids: Vec<Id>) -> Result<RpcResponse<Nothing>> {
+    pub async fn queue_move_top(&self, ids: impl Into<Ids>) -> Result<RpcResponse<Nothing>> {
         self.call(RpcRequest::queue_move_top(ids)).await
     }
 
@@ -504,7 +588,7 @@ impl TransClient {
     ///     dotenv()?;
     ///     env_logger::init();
     ///     let url = env::var("TURL")?;
-    ///     let mut client = if let (Ok(user), Ok(password)) = (env::var("TUSER"), env::var("TPWD")) {
+    ///     let client = if let (Ok(user), Ok(password)) = (env::var("TUSER"), env::var("TPWD")) {
     ///         TransClient::with_auth(url.parse()?, BasicAuth { user, password })
     ///     } else {
     ///         TransClient::new(url.parse()?)
@@ -518,7 +602,7 @@ impl TransClient {
     ///     Ok(())
     /// }
     /// ```
-    pub async fn queue_move_up(&mut self, ids: Vec<Id>) -> Result<RpcResponse<Nothing>> {
+    pub async fn queue_move_up(&self, ids: impl Into<Ids>) -> Result<RpcResponse<Nothing>> {
         self.call(RpcRequest::queue_move_up(ids)).await
     }
 
@@ -541,7 +625,7 @@ impl TransClient {
     ///     dotenv()?;
     ///     env_logger::init();
     ///     let url = env::var("TURL")?;
-    ///     let mut client = if let (Ok(user), Ok(password)) = (env::var("TUSER"), env::var("TPWD")) {
+    ///     let client = if let (Ok(user), Ok(password)) = (env::var("TUSER"), env::var("TPWD")) {
     ///         TransClient::with_auth(url.parse()?, BasicAuth { user, password })
     ///     } else {
     ///         TransClient::new(url.parse()?)
@@ -555,7 +639,7 @@ impl TransClient {
     ///     Ok(())
     /// }
     /// ```
-    pub async fn queue_move_down(&mut self, ids: Vec<Id>) -> Result<RpcResponse<Nothing>> {
+    pub async fn queue_move_down(&self, ids: impl Into<Ids>) -> Result<RpcResponse<Nothing>> {
         self.call(RpcRequest::queue_move_down(ids)).await
     }
 
@@ -578,7 +662,7 @@ impl TransClient {
     ///     dotenv()?;
     ///     env_logger::init();
     ///     let url = env::var("TURL")?;
-    ///     let mut client = if let (Ok(user), Ok(password)) = (env::var("TUSER"), env::var("TPWD")) {
+    ///     let client = if let (Ok(user), Ok(password)) = (env::var("TUSER"), env::var("TPWD")) {
     ///         TransClient::with_auth(url.parse()?, BasicAuth { user, password })
     ///     } else {
     ///         TransClient::new(url.parse()?)
@@ -592,13 +676,14 @@ impl TransClient {
     ///     Ok(())
     /// }
     /// ```
-    pub async fn queue_move_bottom(&mut self, ids: Vec<Id>) -> Result<RpcResponse<Nothing>> {
+    pub async fn queue_move_bottom(&self, ids: impl Into<Ids>) -> Result<RpcResponse<Nothing>> {
         self.call(RpcRequest::queue_move_bottom(ids)).await
     }
 
     /// Performs a torrent get call
     /// fields - if None then ALL fields
-    /// ids - if None then All items
+    /// ids - if None then All items, [`Ids::RecentlyActive`] for the recently changed ones along
+    /// with the ids of the recently removed ones
     ///
     /// # Errors
     ///
@@ -626,7 +711,7 @@ impl TransClient {
     ///         user: env::var("TUSER")?,
     ///         password: env::var("TPWD")?,
     ///     };
-    ///     let mut client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
     ///
     ///     let res: RpcResponse<Torrents<Torrent>> = client.torrent_get(None, None).await?;
     ///     let names: Vec<&String> = res
@@ -688,13 +773,140 @@ impl TransClient {
     /// }
     /// ```
     pub async fn torrent_get(
-        &mut self,
+        &self,
         fields: Option<Vec<TorrentGetField>>,
-        ids: Option<Vec<Id>>,
+        ids: impl Into<Ids>,
     ) -> Result<RpcResponse<Torrents<Torrent>>> {
         self.call(RpcRequest::torrent_get(fields, ids)).await
     }
 
+    /// Performs a torrent get call, deserializing each torrent into the user-defined `T`
+    ///
+    /// Only the fields of `T` are requested, see [`TorrentProjection`].
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error, including a field of `T` missing from the
+    /// response, or [`Error::InvalidArguments`] if `T` requests no field, e.g. a struct using
+    /// `#[serde(flatten)]` without overriding [`TorrentProjection::fields`]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extern crate transmission_rpc;
+    ///
+    /// use std::env;
+    ///
+    /// use dotenvy::dotenv;
+    /// use serde::Deserialize;
+    /// use transmission_rpc::{
+    ///     types::{BasicAuth, Result, TorrentProjection},
+    ///     TransClient,
+    /// };
+    ///
+    /// #[derive(Deserialize, Debug)]
+    /// #[serde(rename_all = "camelCase")]
+    /// struct Row {
+    ///     id: i64,
+    ///     name: String,
+    ///     percent_done: f32,
+    /// }
+    ///
+    /// impl TorrentProjection for Row {}
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     dotenv().ok();
+    ///     env_logger::init();
+    ///     let url = env::var("TURL")?;
+    ///     let basic_auth = BasicAuth {
+    ///         user: env::var("TUSER")?,
+    ///         password: env::var("TPWD")?,
+    ///     };
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///
+    ///     let res = client.torrent_get_as::<Row>(None).await?;
+    ///     for row in res.arguments.torrents {
+    ///         println!("{:5}. {} {:.1}%", row.id, row.name, row.percent_done * 100.0);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn torrent_get_as<T: TorrentProjection>(
+        &self,
+        ids: impl Into<Ids>,
+    ) -> Result<RpcResponse<Torrents<T>>> {
+        let fields = T::fields();
+        if fields.is_empty() {
+            return Err(Error::InvalidArguments {
+                method: "torrent-get",
+                reason: "the projection requests no field",
+            });
+        }
+        self.call(RpcRequest::torrent_get(Some(fields), ids)).await
+    }
+
+    /// Performs a torrent get call in the `"table"` format
+    ///
+    /// Same as [`torrent_get`](Self::torrent_get), but the daemon sends the field names once
+    /// instead of once per torrent, which makes the response of large libraries much smaller
+    /// and faster to decode.
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extern crate transmission_rpc;
+    ///
+    /// use std::env;
+    ///
+    /// use dotenvy::dotenv;
+    /// use transmission_rpc::{
+    ///     types::{BasicAuth, Result, TorrentGetField},
+    ///     TransClient,
+    /// };
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     dotenv().ok();
+    ///     env_logger::init();
+    ///     let url = env::var("TURL")?;
+    ///     let basic_auth = BasicAuth {
+    ///         user: env::var("TUSER")?,
+    ///         password: env::var("TPWD")?,
+    ///     };
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///
+    ///     let res = client
+    ///         .torrent_get_table(
+    ///             Some(vec![TorrentGetField::Id, TorrentGetField::Name]),
+    ///             None,
+    ///         )
+    ///         .await?;
+    ///     println!("{} torrents", res.arguments.torrents.len());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn torrent_get_table(
+        &self,
+        fields: Option<Vec<TorrentGetField>>,
+        ids: impl Into<Ids>,
+    ) -> Result<RpcResponse<Torrents<Torrent>>> {
+        let response: RpcResponse<TorrentTable<Torrent>> = self
+            .call(RpcRequest::torrent_get_table(fields, ids))
+            .await?;
+        Ok(RpcResponse {
+            arguments: response.arguments.into(),
+            result: response.result,
+            tag: response.tag,
+        })
+    }
+
     /// Performs a torrent set call
     /// args - the fields to update
     /// ids - if None then All items
@@ -726,7 +938,7 @@ impl TransClient {
     ///         user: env::var("TUSER")?,
     ///         password: env::var("TPWD")?,
     ///     };
-    ///     let mut client = TransClient::with_auth(url, basic_auth);
+    ///     let client = TransClient::with_auth(url, basic_auth);
     ///
     ///     let mut args = TorrentSetArgs::default();
     ///     args.labels = Some(vec![String::from("blue")]);
@@ -742,9 +954,9 @@ impl TransClient {
     /// }
     /// ```
     pub async fn torrent_set(
-        &mut self,
+        &self,
         args: TorrentSetArgs,
-        ids: Option<Vec<Id>>,
+        ids: impl Into<Ids>,
     ) -> Result<RpcResponse<Nothing>> {
         self.call(RpcRequest::torrent_set(args, ids)).await
     }
@@ -777,7 +989,7 @@ impl TransClient {
     ///         user: env::var("TUSER")?,
     ///         password: env::var("TPWD")?,
     ///     };
-    ///     let mut client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
     ///     let res1: RpcResponse<Nothing> = client
     ///         .torrent_action(TorrentAction::Start, vec![Id::Id(1)])
     ///         .await?;
@@ -791,9 +1003,9 @@ impl TransClient {
     /// }
     /// ```
     pub async fn torrent_action(
-        &mut self,
+        &self,
         action: TorrentAction,
-        ids: Vec<Id>,
+        ids: impl Into<Ids>,
     ) -> Result<RpcResponse<Nothing>> {
         self.call(RpcRequest::torrent_action(action, ids)).await
     }
@@ -826,7 +1038,7 @@ impl TransClient {
     ///         user: env::var("TUSER")?,
     ///         password: env::var("TPWD")?,
     ///     };
-    ///     let mut client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
     ///     let res: RpcResponse<Nothing> = client.torrent_remove(vec![Id::Id(1)], false).await?;
     ///     println!("Remove result: {:?}", &res.is_ok());
     ///
@@ -834,7 +1046,7 @@ impl TransClient {
     /// }
     /// ```
     pub async fn torrent_remove(
-        &mut self,
+        &self,
         ids: Vec<Id>,
         delete_local_data: bool,
     ) -> Result<RpcResponse<Nothing>> {
@@ -870,7 +1082,7 @@ impl TransClient {
     ///         user: env::var("TUSER")?,
     ///         password: env::var("TPWD")?,
     ///     };
-    ///     let mut client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
     ///     let res: RpcResponse<Nothing> = client
     ///         .torrent_set_location(
     ///             vec![Id::Id(1)],
@@ -884,7 +1096,7 @@ impl TransClient {
     /// }
     /// ```
     pub async fn torrent_set_location(
-        &mut self,
+        &self,
         ids: Vec<Id>,
         location: String,
         move_from: Option<bool>,
@@ -921,7 +1133,7 @@ impl TransClient {
     ///         user: env::var("TUSER")?,
     ///         password: env::var("TPWD")?,
     ///     };
-    ///     let mut client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
     ///     let res: RpcResponse<TorrentRenamePath> = client
     ///         .torrent_rename_path(
     ///             vec![Id::Id(1)],
@@ -935,7 +1147,7 @@ impl TransClient {
     /// }
     /// ```
     pub async fn torrent_rename_path(
-        &mut self,
+        &self,
         ids: Vec<Id>,
         path: String,
         name: String,
@@ -948,7 +1160,8 @@ impl TransClient {
     ///
     /// # Errors
     ///
-    /// Any IO Error or Deserialization error
+    /// Any IO Error or Deserialization error, or [`Error::InvalidArguments`] if neither
+    /// `filename` nor `metainfo` is set
     ///
     /// # Example
     ///
@@ -972,14 +1185,10 @@ impl TransClient {
     ///         user: env::var("TUSER")?,
     ///         password: env::var("TPWD")?,
     ///     };
-    ///     let mut client = TransClient::with_auth(url.parse()?, basic_auth);
-    ///     let add: TorrentAddArgs = TorrentAddArgs {
-    ///         filename: Some(
-    ///             "https://releases.ubuntu.com/22.04/ubuntu-22.04.3-desktop-amd64.iso.torrent"
-    ///                 .to_string(),
-    ///         ),
-    ///         ..TorrentAddArgs::default()
-    ///     };
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let add = TorrentAddArgs::from_url(
+    ///         "https://releases.ubuntu.com/22.04/ubuntu-22.04.3-desktop-amd64.iso.torrent",
+    ///     );
     ///     let res: RpcResponse<TorrentAddedOrDuplicate> = client.torrent_add(add).await?;
     ///     println!("Add result: {:?}", &res.is_ok());
     ///     println!("response: {:?}", &res);
@@ -987,81 +1196,669 @@ impl TransClient {
     ///     Ok(())
     /// }
     /// ```
-    ///
-    /// # Panics
-    /// Either metainfo or torrent filename must be set or this call will panic.
     pub async fn torrent_add(
-        &mut self,
+        &self,
         add: TorrentAddArgs,
     ) -> Result<RpcResponse<TorrentAddedOrDuplicate>> {
-        assert!(
-            add.metainfo.is_some() || add.filename.is_some(),
-            "Metainfo or Filename should be provided"
-        );
         self.call(RpcRequest::torrent_add(add)).await
     }
 
-    /// Performs a JRPC call to the server
+    /// Adds a torrent unless the daemon already has it, and returns it with all its fields
+    ///
+    /// A duplicate is not an error: the torrent the daemon already has is returned as
+    /// [`TorrentAddedOrDuplicate::TorrentDuplicate`], so that adding the same torrent again is
+    /// harmless. If `settings` is set, it is applied to the torrent with a torrent set call,
+    /// whether it was just added or not. `TorrentSetArgs::from(&add)` reconciles the labels of
+    /// an existing torrent with `add`; a bandwidth group can be set too.
+    ///
+    /// The `location` of `settings` is handled apart: if an existing torrent is somewhere else,
+    /// its data is moved there with a torrent set location call. A torrent that was just added
+    /// stays in the `download_dir` of `add`. Nothing is moved unless `location` is set.
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error, [`Error::InvalidArguments`] if neither
+    /// `filename` nor `metainfo` is set, [`Error::Rpc`] if the daemon reported a failure, or
+    /// [`Error::UnexpectedResponse`] if it neither added the torrent nor reported a duplicate
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use transmission_rpc::{
+    ///     types::{TorrentAddArgs, TorrentAddedOrDuplicate, TorrentSetArgs},
+    ///     TransClient,
+    /// };
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> transmission_rpc::Result<()> {
+    /// let url = "http://localhost:9091/transmission/rpc".parse().expect("valid URL");
+    /// let client = TransClient::new(url);
+    /// let add = TorrentAddArgs {
+    ///     labels: Some(vec!["linux".to_string()]),
+    ///     ..TorrentAddArgs::from_torrent_file("debian.torrent")?
+    /// };
+    /// let settings = TorrentSetArgs::from(&add)
+    ///     .group("isos".to_string())
+    ///     .location("/downloads/isos".to_string());
+    /// match client.ensure_torrent(add, Some(settings)).await? {
+    ///     TorrentAddedOrDuplicate::TorrentAdded(torrent) => println!("Added {:?}", torrent.name),
+    ///     TorrentAddedOrDuplicate::TorrentDuplicate(torrent) => {
+    ///         println!("Already had {:?}", torrent.name)
+    ///     }
+    ///     TorrentAddedOrDuplicate::Error(_) => unreachable!(),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn ensure_torrent(
+        &self,
+        add: TorrentAddArgs,
+        mut settings: Option<TorrentSetArgs>,
+    ) -> Result<TorrentAddedOrDuplicate> {
+        let response = self.torrent_add(add).await?;
+        let (added, torrent) = match response.arguments {
+            TorrentAddedOrDuplicate::TorrentAdded(torrent) => (true, torrent),
+            TorrentAddedOrDuplicate::TorrentDuplicate(torrent) => (false, torrent),
+            TorrentAddedOrDuplicate::Error(arguments) if response.result == "success" => {
+                return Err(Error::UnexpectedResponse {
+                    method: "torrent-add",
+                    reason: format!("unexpected arguments: {arguments}"),
+                })
+            }
+            TorrentAddedOrDuplicate::Error(_) => {
+                return Err(Error::Rpc {
+                    method: "torrent-add",
+                    tag: response.tag,
+                    code: None,
+                    result: response.result,
+                })
+            }
+        };
+        // The hash, unlike the id, survives a restart of the daemon.
+        let id = torrent
+            .hash_string
+            .map(Id::Hash)
+            .or(torrent.id.map(Id::Id))
+            .ok_or_else(|| Error::UnexpectedResponse {
+                method: "torrent-add",
+                reason: "the torrent has neither an id nor a hash".to_string(),
+            })?;
+        let location = settings
+            .as_mut()
+            .and_then(|settings| settings.location.take());
+        if let Some(settings) = settings {
+            self.torrent_set(settings, vec![id.clone()]).await?;
+        }
+        if let Some(location) = location.filter(|_| !added) {
+            let get = self
+                .torrent_get(Some(vec![TorrentGetField::DownloadDir]), vec![id.clone()])
+                .await?;
+            let current = get.arguments.torrents.first();
+            let moved = current
+                .and_then(|torrent| torrent.download_dir.as_deref())
+                .is_some_and(|current| same_dir(current, &location));
+            if !moved {
+                self.torrent_set_location(vec![id.clone()], location, Some(true))
+                    .await?;
+            }
+        }
+        let get = self.torrent_get(None, vec![id]).await?;
+        let torrent =
+            get.arguments
+                .torrents
+                .into_iter()
+                .next()
+                .ok_or_else(|| Error::UnexpectedResponse {
+                    method: "torrent-get",
+                    reason: "the torrent was removed after being added".to_string(),
+                })?;
+        Ok(if added {
+            TorrentAddedOrDuplicate::TorrentAdded(torrent)
+        } else {
+            TorrentAddedOrDuplicate::TorrentDuplicate(torrent)
+        })
+    }
+
+    /// Performs a group get call
+    /// group - if None then All groups
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extern crate transmission_rpc;
+    ///
+    /// use std::env;
+    ///
+    /// use dotenvy::dotenv;
+    /// use transmission_rpc::{
+    ///     types::{BandwidthGroups, BasicAuth, Result, RpcResponse},
+    ///     TransClient,
+    /// };
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     dotenv().ok();
+    ///     env_logger::init();
+    ///     let url = env::var("TURL")?;
+    ///     let basic_auth = BasicAuth {
+    ///         user: env::var("TUSER")?,
+    ///         password: env::var("TPWD")?,
+    ///     };
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let res: RpcResponse<BandwidthGroups> = client.group_get(None).await?;
+    ///     for group in res.arguments.group {
+    ///         println!("{}: {:?}", group.name, group);
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn group_get(
+        &self,
+        group: Option<Vec<String>>,
+    ) -> Result<RpcResponse<BandwidthGroups>> {
+        self.call(RpcRequest::group_get(group)).await
+    }
+
+    /// Performs a group set call
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extern crate transmission_rpc;
+    ///
+    /// use std::env;
+    ///
+    /// use dotenvy::dotenv;
+    /// use transmission_rpc::{
+    ///     types::{BasicAuth, GroupSetArgs, Nothing, Result, RpcResponse},
+    ///     TransClient,
+    /// };
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     dotenv().ok();
+    ///     env_logger::init();
+    ///     let url = env::var("TURL")?;
+    ///     let basic_auth = BasicAuth {
+    ///         user: env::var("TUSER")?,
+    ///         password: env::var("TPWD")?,
+    ///     };
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let args = GroupSetArgs::new(String::from("tenant-a"))
+    ///         .speed_limit_down(1024)
+    ///         .speed_limit_down_enabled(true);
+    ///     let res: RpcResponse<Nothing> = client.group_set(args).await?;
+    ///     println!("Group-set result: {:?}", &res.is_ok());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn group_set(&self, args: GroupSetArgs) -> Result<RpcResponse<Nothing>> {
+        self.call(RpcRequest::group_set(args)).await
+    }
+
+    /// Watches torrents for changes
+    ///
+    /// The returned stream first yields [`TorrentEvent::Added`] for every existing torrent, then
+    /// polls the daemon for recently active torrents every [`WatchOptions::interval`] and
+    /// yields the changes it observes. Errors are yielded without ending the stream.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extern crate transmission_rpc;
+    ///
+    /// use std::env;
+    ///
+    /// use dotenvy::dotenv;
+    /// use futures_util::StreamExt;
+    /// use transmission_rpc::{
+    ///     types::{BasicAuth, Result},
+    ///     watch::{TorrentEvent, WatchOptions},
+    ///     TransClient,
+    /// };
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     dotenv().ok();
+    ///     env_logger::init();
+    ///     let url = env::var("TURL")?;
+    ///     let basic_auth = BasicAuth {
+    ///         user: env::var("TUSER")?,
+    ///         password: env::var("TPWD")?,
+    ///     };
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let events = client.watch(WatchOptions::default());
+    ///     let mut events = std::pin::pin!(events);
+    ///     while let Some(event) = events.next().await {
+    ///         if let TorrentEvent::Completed(torrent) = event? {
+    ///             println!("{:?} completed", torrent.name);
+    ///             break;
+    ///         }
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn watch(&self, options: WatchOptions) -> impl Stream<Item = Result<TorrentEvent>> + '_ {
+        let fields = options.request_fields();
+        stream::unfold((self, Watcher::default()), move |(client, mut watcher)| {
+            let fields = fields.clone();
+            async move {
+                loop {
+                    if let Some(event) = watcher.next_event() {
+                        return Some((event, (client, watcher)));
+                    }
+                    if watcher.polled() {
+                        tokio::time::sleep(options.interval).await;
+                    }
+                    let ids = if watcher.started() {
+                        Ids::RecentlyActive
+                    } else {
+                        Ids::All
+                    };
+                    match client.torrent_get(Some(fields.clone()), ids).await {
+                        Ok(response) => {
+                            let Torrents { torrents, removed } = response.arguments;
+                            watcher.update(torrents, removed.unwrap_or_default());
+                        }
+                        Err(e) => watcher.push_error(e),
+                    }
+                }
+            }
+        })
+    }
+
+    /// Performs a JRPC call to the server, retrying according to the client's [`RetryPolicy`]
     ///
     /// # Errors
     ///
     /// Any IO Error or Deserialization error
-    async fn call<RS>(&mut self, request: RpcRequest) -> Result<RpcResponse<RS>>
+    async fn call<RS>(&self, request: RpcRequest) -> Result<RpcResponse<RS>>
+    where
+        RS: RpcResponseArgument + DeserializeOwned + std::fmt::Debug,
+    {
+        let retry_policy = self.retry_policy.clone();
+        self.call_with_retry(request, &retry_policy).await
+    }
+
+    /// Performs a JRPC call to the server, retrying according to `retry_policy` instead of the
+    /// client's [`RetryPolicy`]
+    ///
+    /// # Errors
+    ///
+    /// Any IO Error or Deserialization error, once `retry_policy` gives up, or
+    /// [`Error::InvalidArguments`] if `request` is a torrent add without `filename` nor
+    /// `metainfo`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extern crate transmission_rpc;
+    ///
+    /// use std::env;
+    ///
+    /// use dotenvy::dotenv;
+    /// use transmission_rpc::{
+    ///     types::{BasicAuth, Id, Nothing, Result, RpcRequest, RpcResponse},
+    ///     RetryPolicy, TransClient,
+    /// };
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     dotenv().ok();
+    ///     env_logger::init();
+    ///     let url = env::var("TURL")?;
+    ///     let basic_auth = BasicAuth {
+    ///         user: env::var("TUSER")?,
+    ///         password: env::var("TPWD")?,
+    ///     };
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let policy = RetryPolicy {
+    ///         retry_non_idempotent: true,
+    ///         ..RetryPolicy::default()
+    ///     };
+    ///     let res: RpcResponse<Nothing> = client
+    ///         .call_with_retry(RpcRequest::torrent_remove(vec![Id::Id(1)], false), &policy)
+    ///         .await?;
+    ///     println!("Remove result: {:?}", &res.is_ok());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn call_with_retry<RS>(
+        &self,
+        request: RpcRequest,
+        retry_policy: &RetryPolicy,
+    ) -> Result<RpcResponse<RS>>
     where
         RS: RpcResponseArgument + DeserializeOwned + std::fmt::Debug,
     {
+        request.validate()?;
+        let request = self.tagged(request);
+        let call = retry_policy.run(slice::from_ref(&request), || self.send(&request));
+        trace::call(&request, call).await
+    }
+
+    /// Gives `request` a tag of its own, unless it already has one.
+    fn tagged(&self, request: RpcRequest) -> RpcRequest {
+        match request.tag() {
+            Some(_) => request,
+            None => request.with_tag(self.request_id.fetch_add(1, Ordering::Relaxed) + 1),
+        }
+    }
+
+    /// Performs several calls, returning their results in the order of `requests`
+    ///
+    /// With [`Protocol::JsonRpc`] the requests are sent in a single JSON-RPC batch, retried as a
+    /// whole if the client's [`RetryPolicy`] allows every one of them. Otherwise they are
+    /// performed like separate calls, at most `max_concurrency` at a time.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidArguments`] if a request is a torrent add without `filename` nor
+    /// `metainfo`. With [`Protocol::JsonRpc`], also if two requests have the same tag, and any
+    /// IO error or HTTP failure of the batch. The errors of the individual calls are returned in
+    /// their slot.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extern crate transmission_rpc;
+    ///
+    /// use std::env;
+    ///
+    /// use dotenvy::dotenv;
+    /// use transmission_rpc::{
+    ///     types::{BasicAuth, Id, Nothing, Result, RpcRequest, TorrentSetArgs},
+    ///     TransClient,
+    /// };
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<()> {
+    ///     dotenv().ok();
+    ///     env_logger::init();
+    ///     let url = env::var("TURL")?;
+    ///     let basic_auth = BasicAuth {
+    ///         user: env::var("TUSER")?,
+    ///         password: env::var("TPWD")?,
+    ///     };
+    ///     let client = TransClient::with_auth(url.parse()?, basic_auth);
+    ///     let requests = vec![
+    ///         RpcRequest::torrent_set(TorrentSetArgs::new().labels(vec!["a".into()]), vec![Id::Id(1)]),
+    ///         RpcRequest::torrent_set(TorrentSetArgs::new().labels(vec!["b".into()]), vec![Id::Id(2)]),
+    ///     ];
+    ///     let results = client.batch::<Nothing>(requests, 4).await?;
+    ///     for result in results {
+    ///         println!("Set result: {:?}", result?.is_ok());
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn batch<RS>(
+        &self,
+        requests: Vec<RpcRequest>,
+        max_concurrency: usize,
+    ) -> Result<Vec<Result<RpcResponse<RS>>>>
+    where
+        RS: RpcResponseArgument + DeserializeOwned + std::fmt::Debug,
+    {
+        for request in &requests {
+            request.validate()?;
+        }
+        if self.protocol == Protocol::Legacy {
+            return Ok(stream::iter(requests)
+                .map(|request| self.call(request))
+                .buffered(max_concurrency.max(1))
+                .collect()
+                .await);
+        }
+        if requests.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let requests: Vec<RpcRequest> = requests
+            .into_iter()
+            .map(|request| self.tagged(request))
+            .collect();
+        let mut tags = HashSet::new();
+        if !requests.iter().all(|request| tags.insert(tag_of(request))) {
+            return Err(Error::InvalidArguments {
+                method: "batch",
+                reason: "the tags of the requests must be distinct",
+            });
+        }
+        let body: Vec<Value> = requests
+            .iter()
+            .map(|request| jsonrpc::encode_request(request, tag_of(request)))
+            .collect();
+        let body = serde_json::to_vec(&body).expect("RpcRequest is always serializable");
+        debug!(
+            "Request batch of {} calls (tags {:?}): {} bytes",
+            requests.len(),
+            requests.iter().map(tag_of).collect::<Vec<_>>(),
+            body.len()
+        );
+        let call = async {
+            let rsp = self
+                .retry_policy
+                .run(&requests, || self.exchange(body.clone()))
+                .await?;
+            trace::response(rsp.body.len());
+            Ok::<_, Error>(rsp)
+        };
+        let rsp = trace::batch(&requests, call).await?;
+
+        let body = check_status(rsp)?;
+        let mut responses: HashMap<u64, Value> = match serde_json::from_str(&body) {
+            Ok(Value::Array(responses)) => responses
+                .into_iter()
+                .filter_map(|response| Some((response.get("id")?.as_u64()?, response)))
+                .collect(),
+            // A batch rejected as a whole is answered with a single error
+            Ok(response) => {
+                let (code, result) = match jsonrpc::decode_response(requests[0].method(), response)
+                {
+                    Err(e) => (Some(e.code), e.message),
+                    Ok(_) => (None, "batch answered with a single response".to_string()),
+                };
+                return Err(Error::Rpc {
+                    method: requests[0].method(),
+                    tag: None,
+                    code,
+                    result,
+                });
+            }
+            Err(source) => return Err(Error::Decode { source, body }),
+        };
+        Ok(requests
+            .iter()
+            .map(|request| match responses.remove(&tag_of(request)) {
+                Some(response) => parse_response(
+                    response.to_string(),
+                    request,
+                    self.rpc_errors,
+                    self.protocol,
+                ),
+                None => Err(Error::Rpc {
+                    method: request.method(),
+                    tag: request.tag(),
+                    code: None,
+                    result: "missing from the batch response".to_string(),
+                }),
+            })
+            .collect())
+    }
+
+    /// Sends `request` once, renewing the session id as needed
+    async fn send<RS>(&self, request: &RpcRequest) -> Result<RpcResponse<RS>>
+    where
+        RS: RpcResponseArgument + DeserializeOwned + std::fmt::Debug,
+    {
+        let body = match self.protocol {
+            Protocol::Legacy => serde_json::to_vec(request),
+            Protocol::JsonRpc => {
+                serde_json::to_vec(&jsonrpc::encode_request(request, tag_of(request)))
+            }
+        }
+        .expect("RpcRequest is always serializable");
+        debug!(
+            "Request {} (tag {:?}): {} bytes",
+            request.method(),
+            request.tag(),
+            body.len()
+        );
+        let rsp = self.exchange(body).await?;
+        trace::response(rsp.body.len());
+        read_response(rsp, request, self.rpc_errors, self.protocol)
+    }
+
+    /// Posts `body`, renewing the session id as needed, and returns the first non-409 response
+    async fn exchange(&self, body: Vec<u8>) -> Result<HttpResponse> {
+        let session = self.session_id.session();
+        let (mut session_id, mut handshake) = session.current().await;
         let mut remaining_retries = MAX_RETRIES;
         loop {
             remaining_retries = remaining_retries
                 .checked_sub(1)
-                .ok_or(TransError::MaxRetriesReached)?;
-
-            debug!("Loaded auth: {:?}", &self.auth);
-            let rq = match &self.session_id {
-                None => self.rpc_request(),
-                Some(id) => self.rpc_request().header("X-Transmission-Session-Id", id),
-            }
-            .json(&request);
+                .ok_or(Error::MaxRetriesReached)?;
 
-            debug!(
-                "Request body: {:?}",
-                rq.try_clone()
-                    .expect("Unable to get the request body")
-                    .body_string()?
+            let rq = HttpRequest::new(
+                self.url.clone(),
+                self.auth.as_ref(),
+                session_id.as_deref(),
+                body.clone(),
             );
-
-            let rsp: reqwest::Response = rq.send().await?;
-            if matches!(rsp.status(), StatusCode::CONFLICT) {
-                let session_id = rsp
-                    .headers()
-                    .get("X-Transmission-Session-Id")
-                    .ok_or(TransError::NoSessionIdReceived)?
-                    .to_str()?;
-                self.session_id = Some(String::from(session_id));
-
-                debug!("Got new session_id: {}. Retrying request.", session_id);
+            let rsp = self.transport.send(rq).await?;
+            if matches!(rsp.status, StatusCode::CONFLICT) {
+                let fresh = rsp.session_id().ok_or(Error::NoSessionIdReceived)?;
+                let renewed = session
+                    .renew(handshake.take(), session_id.as_deref(), fresh)
+                    .await;
+                debug!("Got new session_id: {}. Retrying request.", renewed);
+                session_id = Some(renewed);
             } else {
-                let rpc_response: RpcResponse<RS> = rsp.json().await?;
-                debug!("Response body: {:#?}", rpc_response);
-
-                return Ok(rpc_response);
+                return Ok(rsp);
             }
         }
     }
+
+    /// Number of times the session id was renewed after a 409 from the daemon, shared between
+    /// the clones of a `SharableTransClient`.
+    #[must_use]
+    pub fn session_renewals(&self) -> u64 {
+        self.session_id.session().renewals()
+    }
+}
+
+/// Turns a non-409 HTTP response into a typed [`RpcResponse`], mapping HTTP, decoding and
+/// (if `rpc_errors` is set) daemon-side failures to [`Error`].
+fn read_response<RS>(
+    rsp: HttpResponse,
+    request: &RpcRequest,
+    rpc_errors: bool,
+    protocol: Protocol,
+) -> Result<RpcResponse<RS>>
+where
+    RS: RpcResponseArgument + DeserializeOwned + std::fmt::Debug,
+{
+    parse_response(check_status(rsp)?, request, rpc_errors, protocol)
 }
 
-trait BodyString {
-    fn body_string(self) -> Result<String>;
+/// Returns the tag of a request tagged by [`RpcClient::tagged`], used as JSON-RPC `id`.
+/// Compares two directories of the daemon, ignoring trailing separators.
+fn same_dir(a: &str, b: &str) -> bool {
+    a.trim_end_matches(['/', '\\']) == b.trim_end_matches(['/', '\\'])
 }
 
-impl BodyString for reqwest::RequestBuilder {
-    fn body_string(self) -> Result<String> {
-        let rq = self.build()?;
-        let body = rq.body().unwrap().as_bytes().unwrap();
-        Ok(std::str::from_utf8(body)?.to_string())
+fn tag_of(request: &RpcRequest) -> u64 {
+    request
+        .tag()
+        .expect("requests are tagged before being sent")
+}
+
+/// Maps HTTP failures to [`Error`], returning the body of successful responses.
+fn check_status(rsp: HttpResponse) -> Result<String> {
+    match rsp.status {
+        StatusCode::UNAUTHORIZED => Err(Error::Unauthorized),
+        StatusCode::FORBIDDEN => Err(Error::Forbidden),
+        status if !status.is_success() => Err(Error::HttpStatus {
+            status,
+            body: rsp.text(),
+        }),
+        _ => Ok(rsp.text()),
     }
 }
 
+/// Decodes the `body` of the response to `request`.
+fn parse_response<RS>(
+    body: String,
+    request: &RpcRequest,
+    rpc_errors: bool,
+    protocol: Protocol,
+) -> Result<RpcResponse<RS>>
+where
+    RS: RpcResponseArgument + DeserializeOwned + std::fmt::Debug,
+{
+    let decoded = match protocol {
+        Protocol::Legacy => serde_json::from_str(&body).map(Ok),
+        Protocol::JsonRpc => serde_json::from_str(&body).and_then(|value| {
+            match jsonrpc::decode_response(request.method(), value) {
+                Ok(legacy) => serde_json::from_value(legacy).map(Ok),
+                Err(e) => Ok(Err(e)),
+            }
+        }),
+    };
+    let rpc_response: RpcResponse<RS> = match decoded {
+        Ok(Ok(rpc_response)) => rpc_response,
+        Ok(Err(e)) => {
+            return Err(Error::Rpc {
+                method: request.method(),
+                tag: request.tag(),
+                code: Some(e.code),
+                result: e.message,
+            })
+        }
+        Err(source) => return Err(Error::Decode { source, body }),
+    };
+    debug!(
+        "Response to {} (tag {:?}): {}, {} bytes",
+        request.method(),
+        request.tag(),
+        rpc_response.result,
+        body.len()
+    );
+
+    if let (Some(sent), Some(received)) = (request.tag(), rpc_response.tag) {
+        if sent != received {
+            return Err(Error::TagMismatch {
+                method: request.method(),
+                sent,
+                received,
+            });
+        }
+    }
+    if rpc_errors && !rpc_response.is_ok() {
+        return Err(Error::Rpc {
+            method: request.method(),
+            tag: request.tag(),
+            code: None,
+            result: rpc_response.result,
+        });
+    }
+    Ok(rpc_response)
+}
+
 #[cfg(test)]
 mod tests {
     use std::env;
@@ -1069,6 +1866,7 @@ mod tests {
     use dotenvy::dotenv;
 
     use super::*;
+    use crate::types::Result;
 
     #[tokio::test]
     pub async fn test_malformed_url() -> Result<()> {
@@ -1076,7 +1874,7 @@ mod tests {
         let _ = env_logger::try_init();
         let url = env::var("TURL")?;
 
-        let mut client;
+        let client;
         if let (Ok(user), Ok(password)) = (env::var("TUSER"), env::var("TPWD")) {
             client = TransClient::with_auth(url.parse()?, BasicAuth { user, password });
         } else {
@@ -1103,4 +1901,405 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn json_rpc_batch() -> Result<()> {
+        use std::future;
+
+        use reqwest::header::HeaderMap;
+        use serde_json::json;
+
+        use crate::transport::TransportFuture;
+
+        /// Answers a batch in reverse order, failing the calls on torrent 2.
+        struct Daemon;
+
+        impl Transport for Daemon {
+            fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+                let requests: Vec<Value> = serde_json::from_slice(&request.body).unwrap();
+                let responses: Vec<Value> = requests
+                    .iter()
+                    .rev()
+                    .map(|request| match request["params"]["ids"] == json!([2]) {
+                        true => json!({
+                            "jsonrpc": "2.0",
+                            "id": request["id"],
+                            "error": { "code": -32602, "message": "invalid params" }
+                        }),
+                        false => json!({ "jsonrpc": "2.0", "id": request["id"], "result": {} }),
+                    })
+                    .collect();
+                Box::pin(future::ready(Ok(HttpResponse {
+                    status: StatusCode::OK,
+                    headers: HeaderMap::new(),
+                    body: serde_json::to_vec(&responses).unwrap(),
+                })))
+            }
+        }
+
+        let client = TransClientBuilder::new("http://localhost:9091/transmission/rpc".parse()?)
+            .transport(Daemon)
+            .protocol(Protocol::JsonRpc)
+            .build()?;
+        let requests = (1..=3)
+            .map(|id| RpcRequest::torrent_action(TorrentAction::Start, vec![Id::Id(id)]))
+            .collect();
+        let results = client.batch::<Nothing>(requests, 1).await?;
+        assert!(results[0].is_ok());
+        assert!(matches!(
+            results[1],
+            Err(Error::Rpc {
+                code: Some(-32602),
+                ..
+            })
+        ));
+        assert!(results[2].is_ok());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn json_rpc_batch_rejects_duplicate_tags() -> Result<()> {
+        let client = TransClientBuilder::new("http://localhost:9091/transmission/rpc".parse()?)
+            .protocol(Protocol::JsonRpc)
+            .build()?;
+        let requests = (1..=2)
+            .map(|id| {
+                RpcRequest::torrent_action(TorrentAction::Start, vec![Id::Id(id)]).with_tag(7)
+            })
+            .collect();
+        let result = client.batch::<Nothing>(requests, 1).await;
+        assert!(matches!(
+            result,
+            Err(Error::InvalidArguments {
+                method: "batch",
+                ..
+            })
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn legacy_batch() -> Result<()> {
+        use crate::{mock::MockServer, types::TorrentSetArgs};
+
+        let server = MockServer::start().await?;
+        let client = server.client();
+        for hash in [
+            "e08c426aab2cc58649ae5e73690e3747117b3470",
+            "0a1b2c3d4e5f60718293a4b5c6d7e8f901234567",
+        ] {
+            client
+                .torrent_add(TorrentAddArgs {
+                    filename: Some(format!("magnet:?xt=urn:btih:{hash}")),
+                    ..TorrentAddArgs::default()
+                })
+                .await?;
+        }
+
+        let requests = vec![
+            RpcRequest::torrent_set(
+                TorrentSetArgs::new().labels(vec!["a".into()]),
+                vec![Id::Id(1)],
+            ),
+            RpcRequest::torrent_set(
+                TorrentSetArgs::new().labels(vec!["b".into()]),
+                vec![Id::Id(2)],
+            ),
+            RpcRequest::torrent_get(Some(vec![TorrentGetField::Id]), Ids::All),
+        ];
+        let results = client.batch::<Nothing>(requests, 2).await?;
+        assert_eq!(results.len(), 3);
+        assert!(results
+            .iter()
+            .all(|result| result.as_ref().is_ok_and(|rsp| rsp.is_ok())));
+
+        let torrents = client
+            .torrent_get(Some(vec![TorrentGetField::Labels]), Ids::All)
+            .await?
+            .arguments
+            .torrents;
+        assert_eq!(torrents[0].labels, Some(vec!["a".to_string()]));
+        assert_eq!(torrents[1].labels, Some(vec!["b".to_string()]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tags_are_validated() -> Result<()> {
+        use std::future;
+
+        use reqwest::header::HeaderMap;
+
+        use crate::transport::TransportFuture;
+
+        /// Answers every request with the tag 999.
+        struct Proxy;
+
+        impl Transport for Proxy {
+            fn send(&self, _request: HttpRequest) -> TransportFuture<'_> {
+                Box::pin(future::ready(Ok(HttpResponse {
+                    status: StatusCode::OK,
+                    headers: HeaderMap::new(),
+                    body: br#"{"arguments":{},"result":"success","tag":999}"#.to_vec(),
+                })))
+            }
+        }
+
+        let client = TransClientBuilder::new("http://localhost:9091/transmission/rpc".parse()?)
+            .transport(Proxy)
+            .build()?;
+        let error = client.session_close().await.unwrap_err();
+        assert!(matches!(
+            error,
+            Error::TagMismatch {
+                sent: 1,
+                received: 999,
+                ..
+            }
+        ));
+        let response: RpcResponse<Nothing> = client
+            .call_with_retry(
+                RpcRequest::session_close().with_tag(999),
+                &RetryPolicy::none(),
+            )
+            .await?;
+        assert_eq!(response.tag, Some(999));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn responses_are_mapped_to_errors() -> Result<()> {
+        use std::future;
+
+        use reqwest::header::HeaderMap;
+
+        use crate::transport::TransportFuture;
+
+        /// Answers every request with the same status and body.
+        struct Stub(StatusCode, &'static str);
+
+        impl Transport for Stub {
+            fn send(&self, _request: HttpRequest) -> TransportFuture<'_> {
+                Box::pin(future::ready(Ok(HttpResponse {
+                    status: self.0,
+                    headers: HeaderMap::new(),
+                    body: self.1.as_bytes().to_vec(),
+                })))
+            }
+        }
+
+        let url: Url = "http://localhost:9091/transmission/rpc".parse()?;
+        let client = |status, body, rpc_errors| {
+            TransClientBuilder::new(url.clone())
+                .transport(Stub(status, body))
+                .retry_policy(RetryPolicy::none())
+                .rpc_errors(rpc_errors)
+                .build()
+        };
+
+        let error = client(StatusCode::FORBIDDEN, "", false)?
+            .session_close()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Forbidden));
+
+        let error = client(StatusCode::BAD_GATEWAY, "upstream down", false)?
+            .session_close()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::HttpStatus { status: StatusCode::BAD_GATEWAY, ref body } if body == "upstream down"
+        ));
+
+        let error = client(StatusCode::OK, "<html>", false)?
+            .session_close()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Decode { ref body, .. } if body == "<html>"));
+
+        let failure = r#"{"arguments":{},"result":"no such torrent"}"#;
+        let response = client(StatusCode::OK, failure, false)?
+            .session_close()
+            .await?;
+        assert!(!response.is_ok());
+        let error = client(StatusCode::OK, failure, true)?
+            .session_close()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Rpc { method: "session-close", ref result, .. } if result == "no such torrent"
+        ));
+
+        let error = client(
+            StatusCode::OK,
+            r#"{"arguments":{},"result":"success"}"#,
+            true,
+        )?
+        .ensure_torrent(
+            TorrentAddArgs::from_url("http://example.com/a.torrent"),
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::UnexpectedResponse {
+                method: "torrent-add",
+                ..
+            }
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn tags_are_echoed() -> Result<()> {
+        let server = mock::MockServer::start().await?;
+        let client = server.client();
+        assert_eq!(client.session_stats().await?.tag, Some(1));
+        assert_eq!(client.session_stats().await?.tag, Some(2));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn torrent_add_without_source_is_rejected() -> Result<()> {
+        let client = TransClient::new("http://localhost:9091/transmission/rpc".parse()?);
+        let error = client
+            .torrent_add(TorrentAddArgs::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::InvalidArguments {
+                method: "torrent-add",
+                ..
+            }
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn torrent_add_requests_without_source_are_rejected() -> Result<()> {
+        let client = TransClient::new("http://localhost:9091/transmission/rpc".parse()?);
+        let add = || RpcRequest::torrent_add(TorrentAddArgs::default());
+        let error = client
+            .call_with_retry::<TorrentAddedOrDuplicate>(add(), &RetryPolicy::none())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::InvalidArguments {
+                method: "torrent-add",
+                ..
+            }
+        ));
+        for protocol in [Protocol::Legacy, Protocol::JsonRpc] {
+            let client = TransClientBuilder::new("http://localhost:9091/transmission/rpc".parse()?)
+                .protocol(protocol)
+                .build()?;
+            let result = client
+                .batch::<TorrentAddedOrDuplicate>(vec![add()], 1)
+                .await;
+            assert!(matches!(
+                result,
+                Err(Error::InvalidArguments {
+                    method: "torrent-add",
+                    ..
+                })
+            ));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn torrent_get_as_without_fields_is_rejected() -> Result<()> {
+        #[derive(serde::Deserialize, Debug)]
+        struct Flattened {
+            #[serde(flatten)]
+            #[allow(dead_code)]
+            torrent: Torrent,
+        }
+
+        impl TorrentProjection for Flattened {}
+
+        assert!(Flattened::fields().is_empty());
+        let client = TransClient::new("http://localhost:9091/transmission/rpc".parse()?);
+        let error = client.torrent_get_as::<Flattened>(None).await.unwrap_err();
+        assert!(matches!(
+            error,
+            Error::InvalidArguments {
+                method: "torrent-get",
+                ..
+            }
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn ensure_torrent_is_idempotent() -> Result<()> {
+        let server = mock::MockServer::start().await?;
+        let client = server.client();
+        let add = TorrentAddArgs {
+            labels: Some(vec!["linux".to_string()]),
+            ..TorrentAddArgs::from_magnet(
+                "magnet:?xt=urn:btih:e08c426aab2cc58649ae5e73690e3747117b3470&dn=debian.iso",
+            )
+        };
+        let TorrentAddedOrDuplicate::TorrentAdded(added) =
+            client.ensure_torrent(add.clone(), None).await?
+        else {
+            panic!("the torrent should have been added");
+        };
+        assert_eq!(added.name.as_deref(), Some("debian.iso"));
+        assert_eq!(added.download_dir.as_deref(), Some("/downloads/complete"));
+
+        // The data is only moved when asked for
+        let add = TorrentAddArgs {
+            labels: Some(vec!["iso".to_string()]),
+            download_dir: Some("/downloads/isos".to_string()),
+            ..add
+        };
+        let settings = TorrentSetArgs::from(&add).group("isos".to_string());
+        let TorrentAddedOrDuplicate::TorrentDuplicate(duplicate) =
+            client.ensure_torrent(add.clone(), Some(settings)).await?
+        else {
+            panic!("the torrent should have been a duplicate");
+        };
+        assert_eq!(duplicate.id, added.id);
+        assert_eq!(duplicate.labels, Some(vec!["iso".to_string()]));
+        assert_eq!(duplicate.group.as_deref(), Some("isos"));
+        assert_eq!(
+            duplicate.download_dir.as_deref(),
+            Some("/downloads/complete")
+        );
+
+        let settings = TorrentSetArgs::new().location("/downloads/isos".to_string());
+        let duplicate = client.ensure_torrent(add.clone(), Some(settings)).await?;
+        let TorrentAddedOrDuplicate::TorrentDuplicate(duplicate) = duplicate else {
+            panic!("the torrent should have been a duplicate");
+        };
+        assert_eq!(duplicate.download_dir.as_deref(), Some("/downloads/isos"));
+
+        // torrent-add, torrent-set and two torrent-get, but no torrent-set-location
+        let requests = server.request_count();
+        let settings = TorrentSetArgs::new().location("/downloads/isos/".to_string());
+        client.ensure_torrent(add, Some(settings)).await?;
+        assert_eq!(server.request_count(), requests + 4);
+
+        let error = client
+            .ensure_torrent(TorrentAddArgs::from_magnet("magnet:?dn=nothing"), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            Error::Rpc {
+                method: "torrent-add",
+                ..
+            }
+        ));
+        Ok(())
+    }
 }
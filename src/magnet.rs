@@ -0,0 +1,331 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+//! Parsing and building of `magnet:` URIs, see [`MagnetLink`].
+
+use std::{fmt, str::FromStr};
+
+use reqwest::Url;
+
+use crate::{
+    metainfo::Metainfo,
+    types::{Id, TorrentAddArgs},
+};
+
+/// Multihash prefix of a SHA-256 digest, as found in `urn:btmh:` info-hashes.
+const SHA256_MULTIHASH: &str = "1220";
+
+/// A `magnet:` URI, as returned in [`Torrent::magnet_link`](crate::types::Torrent::magnet_link)
+/// and accepted by [`TorrentAddArgs`].
+///
+/// Links are parsed with [`str::parse`] and written back with [`to_string`](ToString::to_string):
+///
+/// ```
+/// use transmission_rpc::{magnet::MagnetLink, types::TorrentAddArgs};
+///
+/// # fn main() -> Result<(), transmission_rpc::magnet::ParseMagnetError> {
+/// let link: MagnetLink =
+///     "magnet:?xt=urn:btih:E08C426AAB2CC58649AE5E73690E3747117B3470&dn=debian+12.iso".parse()?;
+/// assert_eq!(link.hash_string(), "e08c426aab2cc58649ae5e73690e3747117b3470");
+/// assert_eq!(link.display_name.as_deref(), Some("debian 12.iso"));
+///
+/// let link = MagnetLink {
+///     trackers: vec!["udp://tracker.example:6969/announce".to_string()],
+///     ..link
+/// };
+/// let add = TorrentAddArgs::from(&link);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Info-hashes are kept as lowercase hex digits, whether they were written in hex or base32.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MagnetLink {
+    /// SHA-1 info-hash (`xt=urn:btih:`) as 40 hex digits, for v1 and hybrid torrents.
+    pub info_hash: Option<String>,
+    /// SHA-256 info-hash (`xt=urn:btmh:`) as 64 hex digits, for v2 and hybrid torrents.
+    pub info_hash_v2: Option<String>,
+    /// `dn`
+    pub display_name: Option<String>,
+    /// `tr`
+    pub trackers: Vec<String>,
+    /// `ws`
+    pub web_seeds: Vec<String>,
+    /// `xl`, in bytes
+    pub exact_length: Option<u64>,
+}
+
+impl MagnetLink {
+    /// The info-hash the daemon reports in
+    /// [`Torrent::hash_string`](crate::types::Torrent::hash_string), the SHA-1 one unless the
+    /// torrent is v2 only.
+    ///
+    /// # Panics
+    ///
+    /// If neither `info_hash` nor `info_hash_v2` is set, which cannot happen to a parsed link
+    #[must_use]
+    pub fn hash_string(&self) -> &str {
+        self.info_hash
+            .as_deref()
+            .or(self.info_hash_v2.as_deref())
+            .expect("a magnet link has at least one info-hash")
+    }
+
+    /// Identifies this torrent in calls, e.g. to find out if the daemon already has it.
+    #[must_use]
+    pub fn id(&self) -> Id {
+        Id::Hash(self.hash_string().to_string())
+    }
+}
+
+/// The magnet link of a `.torrent` file, with its name, trackers, web seeds and size.
+impl From<&Metainfo> for MagnetLink {
+    fn from(metainfo: &Metainfo) -> Self {
+        MagnetLink {
+            info_hash: metainfo.info_hash.clone(),
+            info_hash_v2: metainfo.info_hash_v2.clone(),
+            display_name: Some(metainfo.name.clone()),
+            trackers: metainfo.trackers.concat(),
+            web_seeds: metainfo.web_seeds.clone(),
+            exact_length: Some(metainfo.total_size),
+        }
+    }
+}
+
+impl From<&MagnetLink> for TorrentAddArgs {
+    fn from(link: &MagnetLink) -> Self {
+        TorrentAddArgs::from_magnet(link.to_string())
+    }
+}
+
+impl From<MagnetLink> for TorrentAddArgs {
+    fn from(link: MagnetLink) -> Self {
+        TorrentAddArgs::from(&link)
+    }
+}
+
+impl FromStr for MagnetLink {
+    type Err = ParseMagnetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let url = Url::parse(s).map_err(|_| ParseMagnetError("not a URI"))?;
+        if url.scheme() != "magnet" {
+            return Err(ParseMagnetError("not a magnet: URI"));
+        }
+        let mut link = MagnetLink {
+            info_hash: None,
+            info_hash_v2: None,
+            display_name: None,
+            trackers: Vec::new(),
+            web_seeds: Vec::new(),
+            exact_length: None,
+        };
+        for (key, value) in url.query_pairs() {
+            // Parameters may be numbered when repeated, e.g. `tr.1`.
+            match key.split('.').next().unwrap_or_default() {
+                "xt" => {
+                    if let Some(hash) = value.strip_prefix("urn:btih:") {
+                        link.info_hash = Some(parse_btih(hash)?);
+                    } else if let Some(hash) = value.strip_prefix("urn:btmh:") {
+                        link.info_hash_v2 = Some(parse_btmh(hash)?);
+                    }
+                }
+                "dn" => link.display_name = Some(value.into_owned()),
+                "tr" => link.trackers.push(value.into_owned()),
+                "ws" => link.web_seeds.push(value.into_owned()),
+                "xl" => {
+                    let length = value.parse().map_err(|_| ParseMagnetError("invalid xl"))?;
+                    link.exact_length = Some(length);
+                }
+                _ => {}
+            }
+        }
+        if link.info_hash.is_none() && link.info_hash_v2.is_none() {
+            return Err(ParseMagnetError("no urn:btih: or urn:btmh: info-hash"));
+        }
+        Ok(link)
+    }
+}
+
+impl fmt::Display for MagnetLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut separator = "?";
+        let mut param = |f: &mut fmt::Formatter<'_>, key, value: &str| {
+            let result = write!(f, "{separator}{key}={value}");
+            separator = "&";
+            result
+        };
+        f.write_str("magnet:")?;
+        if let Some(hash) = &self.info_hash {
+            param(f, "xt", &format!("urn:btih:{hash}"))?;
+        }
+        if let Some(hash) = &self.info_hash_v2 {
+            param(f, "xt", &format!("urn:btmh:{SHA256_MULTIHASH}{hash}"))?;
+        }
+        if let Some(name) = &self.display_name {
+            param(f, "dn", &encode(name))?;
+        }
+        if let Some(length) = self.exact_length {
+            param(f, "xl", &length.to_string())?;
+        }
+        for tracker in &self.trackers {
+            param(f, "tr", &encode(tracker))?;
+        }
+        for web_seed in &self.web_seeds {
+            param(f, "ws", &encode(web_seed))?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when parsing a [`MagnetLink`] fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMagnetError(&'static str);
+
+impl fmt::Display for ParseMagnetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid magnet link: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseMagnetError {}
+
+/// Reads a v1 info-hash, written as 40 hex digits or 32 base32 digits.
+fn parse_btih(hash: &str) -> Result<String, ParseMagnetError> {
+    match hash.len() {
+        40 if hash.bytes().all(|b| b.is_ascii_hexdigit()) => Ok(hash.to_ascii_lowercase()),
+        32 => base32_to_hex(hash).ok_or(ParseMagnetError("invalid base32 info-hash")),
+        _ => Err(ParseMagnetError("invalid urn:btih: info-hash")),
+    }
+}
+
+/// Reads a v2 info-hash, a SHA-256 multihash in hex.
+fn parse_btmh(hash: &str) -> Result<String, ParseMagnetError> {
+    hash.strip_prefix(SHA256_MULTIHASH)
+        .filter(|hash| hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+        .map(str::to_ascii_lowercase)
+        .ok_or(ParseMagnetError("invalid urn:btmh: info-hash"))
+}
+
+/// Converts 32 digits of RFC 4648 base32, i.e. 160 bits, to 40 hex digits.
+fn base32_to_hex(digits: &str) -> Option<String> {
+    let mut bits: u64 = 0;
+    let mut count = 0;
+    let mut hex = String::with_capacity(40);
+    for digit in digits.bytes() {
+        let value = match digit.to_ascii_uppercase() {
+            c @ b'A'..=b'Z' => c - b'A',
+            c @ b'2'..=b'7' => c - b'2' + 26,
+            _ => return None,
+        };
+        bits = bits << 5 | u64::from(value);
+        count += 5;
+        while count >= 4 {
+            count -= 4;
+            hex.push(char::from_digit(((bits >> count) & 0xf) as u32, 16)?);
+        }
+    }
+    Some(hex)
+}
+
+/// Percent-encodes everything but unreserved characters.
+pub(crate) fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                char::from(b).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{MagnetLink, ParseMagnetError};
+    use crate::{metainfo::Metainfo, types::Torrent};
+
+    const HASH: &str = "e08c426aab2cc58649ae5e73690e3747117b3470";
+
+    #[test]
+    fn round_trip() -> Result<(), ParseMagnetError> {
+        let uri = format!(
+            "magnet:?xt=urn:btih:{HASH}&dn=debian%2012.iso&xl=42\
+             &tr=udp%3A%2F%2Ftracker.example%3A6969%2Fannounce&ws=http%3A%2F%2Fseed%2F"
+        );
+        let link: MagnetLink = uri.parse()?;
+        assert_eq!(link.info_hash.as_deref(), Some(HASH));
+        assert_eq!(link.display_name.as_deref(), Some("debian 12.iso"));
+        assert_eq!(link.trackers, ["udp://tracker.example:6969/announce"]);
+        assert_eq!(link.web_seeds, ["http://seed/"]);
+        assert_eq!(link.exact_length, Some(42));
+        assert_eq!(link.to_string(), uri);
+        Ok(())
+    }
+
+    #[test]
+    fn base32_and_v2_hashes() -> Result<(), ParseMagnetError> {
+        let v2 = "5570d73e005c78b5510a873a6353b5270a9e0fd2862bb9dde7c21f53e5bdcbbd";
+        let link: MagnetLink = format!(
+            "magnet:?xt=urn:btih:4CGEE2VLFTCYMSNOLZZWSDRXI4IXWNDQ&xt=urn:btmh:1220{v2}&tr.1=a&tr.2=b"
+        )
+        .parse()?;
+        assert_eq!(link.info_hash.as_deref(), Some(HASH));
+        assert_eq!(link.info_hash_v2.as_deref(), Some(v2));
+        assert_eq!(link.trackers, ["a", "b"]);
+
+        let link: MagnetLink = format!("magnet:?xt=urn:btmh:1220{v2}").parse()?;
+        assert_eq!(link.hash_string(), v2);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        for uri in [
+            "http://example.com/?xt=urn:btih:e08c426aab2cc58649ae5e73690e3747117b3470",
+            "magnet:?dn=nothing",
+            "magnet:?xt=urn:btih:e08c",
+            "magnet:?xt=urn:btih:0000000000000000000000000000000!",
+            "magnet:?xt=urn:btmh:1114e08c426aab2cc58649ae5e73690e3747117b3470",
+            "magnet:?xt=urn:btih:e08c426aab2cc58649ae5e73690e3747117b3470&xl=-1",
+        ] {
+            assert!(uri.parse::<MagnetLink>().is_err(), "{uri}");
+        }
+    }
+
+    #[test]
+    fn from_metainfo() -> std::io::Result<()> {
+        let metainfo = Metainfo::from_bytes(
+            &b"d8:announce9:http://a/4:infod6:lengthi3e4:name3:a b12:piece lengthi1e6:pieces20:aaaaaaaaaaaaaaaaaaaaee"[..],
+        )?;
+        let link = MagnetLink::from(&metainfo);
+        assert_eq!(
+            link.to_string(),
+            format!(
+                "magnet:?xt=urn:btih:{}&dn=a%20b&xl=3&tr=http%3A%2F%2Fa%2F",
+                metainfo.hash_string()
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn torrent_magnet() -> serde_json::Result<()> {
+        let torrent: Torrent = serde_json::from_value(json!({ "magnetLink": "" }))?;
+        assert_eq!(torrent.magnet(), None);
+
+        let torrent: Torrent = serde_json::from_value(json!({
+            "magnetLink": format!("magnet:?xt=urn:btih:{HASH}&dn=archlinux-2024.08.01-x86_64.iso")
+        }))?;
+        let link = torrent.magnet().expect("a valid magnet link");
+        assert_eq!(link.hash_string(), HASH);
+        assert_eq!(
+            link.display_name.as_deref(),
+            Some("archlinux-2024.08.01-x86_64.iso")
+        );
+        Ok(())
+    }
+}
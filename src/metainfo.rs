@@ -0,0 +1,521 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+//! Parsing of `.torrent` files, see [`Metainfo`].
+//!
+//! Knowing the info-hash and the files of a torrent before adding it makes it possible to
+//! skip torrents the daemon already has and to select files up front:
+//!
+//! ```no_run
+//! use transmission_rpc::{metainfo::Metainfo, types::TorrentAddArgs, TransClient};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> transmission_rpc::Result<()> {
+//! let url = "http://localhost:9091/transmission/rpc".parse().expect("valid URL");
+//! let client = TransClient::new(url);
+//! let metainfo = Metainfo::from_file("debian.torrent")?;
+//! let known = client.torrent_get(None, Some(vec![metainfo.id()])).await?;
+//! if known.arguments.torrents.is_empty() {
+//!     client
+//!         .torrent_add(TorrentAddArgs {
+//!             paused: Some(true),
+//!             ..TorrentAddArgs::from(&metainfo)
+//!         })
+//!         .await?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Both BitTorrent v1 and v2 ([BEP 52](https://www.bittorrent.org/beps/bep_0052.html))
+//! torrents are supported, as well as hybrid torrents carrying both. Malformed files are
+//! rejected with an [`io::Error`] of kind [`InvalidData`](io::ErrorKind::InvalidData).
+
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+use crate::types::{Id, TorrentAddArgs};
+
+/// Nesting allowed in a bencoded document, deep enough for any v2 file tree.
+const MAX_DEPTH: usize = 256;
+
+/// The content of a `.torrent` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metainfo {
+    /// Name of the torrent, i.e. of its single file or of its root directory.
+    pub name: String,
+    /// SHA-1 info-hash as 40 lowercase hex digits, for v1 and hybrid torrents.
+    pub info_hash: Option<String>,
+    /// SHA-256 info-hash as 64 lowercase hex digits, for v2 and hybrid torrents.
+    pub info_hash_v2: Option<String>,
+    /// Files of the torrent, in the order of their indices in `files_wanted`, `priority_high`,
+    /// etc. Padding files of hybrid torrents are left out, as the daemon does.
+    pub files: Vec<MetainfoFile>,
+    /// Sum of the lengths of `files`.
+    pub total_size: u64,
+    pub piece_length: u64,
+    pub private: bool,
+    /// Announce urls, grouped by [tier](https://www.bittorrent.org/beps/bep_0012.html).
+    pub trackers: Vec<Vec<String>>,
+    /// [Web seeds](https://www.bittorrent.org/beps/bep_0019.html).
+    pub web_seeds: Vec<String>,
+    pub comment: Option<String>,
+    pub created_by: Option<String>,
+    bytes: Vec<u8>,
+}
+
+/// A file of a [`Metainfo`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetainfoFile {
+    /// Path of the file including the name of the torrent, separated by `/`, as in
+    /// [`File::name`](crate::types::File::name).
+    pub name: String,
+    pub length: u64,
+}
+
+impl Metainfo {
+    /// Parses the content of a `.torrent` file.
+    ///
+    /// # Errors
+    ///
+    /// If `bytes` is not valid bencode or lacks a field required in a `.torrent` file
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> io::Result<Self> {
+        let bytes = bytes.into();
+        let (root, info_span) = Parser::new(&bytes).document()?;
+        let info = root
+            .get("info")
+            .ok_or_else(|| invalid("missing info dictionary"))?;
+        let raw_info = info_span.map_or(&[][..], |(start, end)| &bytes[start..end]);
+
+        let name = info
+            .text("name.utf-8")
+            .or_else(|| info.text("name"))
+            .ok_or_else(|| invalid("missing name"))?;
+        let piece_length = info
+            .get("piece length")
+            .and_then(Value::length)
+            .ok_or_else(|| invalid("missing piece length"))?;
+        let v1 = info.get("pieces").is_some();
+        let v2 = info.get("meta version").and_then(Value::int) == Some(2);
+        if !v1 && !v2 {
+            return Err(invalid("neither v1 pieces nor v2 meta version"));
+        }
+
+        let mut files = Vec::new();
+        if let Some(Value::List(entries)) = info.get("files").filter(|_| v1) {
+            for entry in entries {
+                let padding = entry
+                    .get("attr")
+                    .and_then(Value::bytes)
+                    .is_some_and(|attr| attr.contains(&b'p'));
+                let path = entry
+                    .get("path.utf-8")
+                    .or_else(|| entry.get("path"))
+                    .and_then(Value::texts)
+                    .ok_or_else(|| invalid("file without path"))?;
+                let length = file_length(entry)?;
+                if !padding {
+                    files.push(MetainfoFile {
+                        name: format!("{name}/{}", path.join("/")),
+                        length,
+                    });
+                }
+            }
+        } else if v1 {
+            files.push(MetainfoFile {
+                name: name.clone(),
+                length: file_length(info)?,
+            });
+        } else {
+            let tree = info
+                .get("file tree")
+                .ok_or_else(|| invalid("missing file tree"))?;
+            walk_file_tree(tree, &name, 0, &mut files)?;
+        }
+
+        let trackers = match root.get("announce-list") {
+            Some(Value::List(tiers)) => tiers
+                .iter()
+                .filter_map(Value::texts)
+                .filter(|tier| !tier.is_empty())
+                .collect(),
+            _ => root
+                .text("announce")
+                .map(|announce| vec![vec![announce]])
+                .unwrap_or_default(),
+        };
+        let web_seeds = match root.get("url-list") {
+            Some(Value::Bytes(url)) if !url.is_empty() => vec![text(url)],
+            Some(list @ Value::List(_)) => list.texts().unwrap_or_default(),
+            _ => Vec::new(),
+        };
+
+        Ok(Metainfo {
+            info_hash: v1.then(|| hex(&Sha1::digest(raw_info))),
+            info_hash_v2: v2.then(|| hex(&Sha256::digest(raw_info))),
+            total_size: files.iter().map(|file| file.length).sum(),
+            files,
+            name,
+            piece_length,
+            private: info.get("private").and_then(Value::int) == Some(1),
+            trackers,
+            web_seeds,
+            comment: root.text("comment.utf-8").or_else(|| root.text("comment")),
+            created_by: root.text("created by"),
+            bytes,
+        })
+    }
+
+    /// Reads and parses the `.torrent` file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be read or is not a valid `.torrent` file
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_bytes(fs::read(path)?)
+    }
+
+    /// The info-hash the daemon reports in [`Torrent::hash_string`](crate::types::Torrent::hash_string),
+    /// the SHA-1 one unless the torrent is v2 only.
+    #[must_use]
+    pub fn hash_string(&self) -> &str {
+        self.info_hash
+            .as_deref()
+            .or(self.info_hash_v2.as_deref())
+            .expect("a parsed torrent has at least one info-hash")
+    }
+
+    /// Identifies this torrent in calls, e.g. to find out if the daemon already has it.
+    #[must_use]
+    pub fn id(&self) -> Id {
+        Id::Hash(self.hash_string().to_string())
+    }
+
+    /// The `.torrent` file this was parsed from.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl From<&Metainfo> for TorrentAddArgs {
+    fn from(metainfo: &Metainfo) -> Self {
+        TorrentAddArgs::from_bytes(&metainfo.bytes)
+    }
+}
+
+impl From<Metainfo> for TorrentAddArgs {
+    fn from(metainfo: Metainfo) -> Self {
+        TorrentAddArgs::from(&metainfo)
+    }
+}
+
+fn file_length(entry: &Value<'_>) -> io::Result<u64> {
+    entry
+        .get("length")
+        .and_then(Value::length)
+        .ok_or_else(|| invalid("file without length"))
+}
+
+/// Appends the files of a v2 `file tree` to `files`, in the (sorted) order of its keys.
+fn walk_file_tree(
+    node: &Value<'_>,
+    path: &str,
+    depth: usize,
+    files: &mut Vec<MetainfoFile>,
+) -> io::Result<()> {
+    let Value::Dict(entries) = node else {
+        return Err(invalid("malformed file tree"));
+    };
+    for (key, child) in entries {
+        if key.is_empty() {
+            files.push(MetainfoFile {
+                name: path.to_string(),
+                length: file_length(child)?,
+            });
+        } else {
+            let path = format!("{path}/{}", text(key));
+            walk_file_tree(child, &path, depth + 1, files)?;
+        }
+    }
+    if depth == 0 && files.is_empty() {
+        return Err(invalid("empty file tree"));
+    }
+    Ok(())
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid metainfo: {reason}"),
+    )
+}
+
+fn text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// A bencoded value, borrowing its strings from the document.
+#[derive(Debug)]
+enum Value<'a> {
+    Int(i64),
+    Bytes(&'a [u8]),
+    List(Vec<Value<'a>>),
+    Dict(BTreeMap<&'a [u8], Value<'a>>),
+}
+
+impl<'a> Value<'a> {
+    fn get(&self, key: &str) -> Option<&Value<'a>> {
+        match self {
+            Value::Dict(entries) => entries.get(key.as_bytes()),
+            _ => None,
+        }
+    }
+
+    fn int(&self) -> Option<i64> {
+        match self {
+            Value::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn length(&self) -> Option<u64> {
+        self.int().and_then(|value| u64::try_from(value).ok())
+    }
+
+    fn bytes(&self) -> Option<&'a [u8]> {
+        match self {
+            Value::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    fn text(&self, key: &str) -> Option<String> {
+        self.get(key).and_then(Value::bytes).map(text)
+    }
+
+    /// The elements of a list of strings.
+    fn texts(&self) -> Option<Vec<String>> {
+        match self {
+            Value::List(items) => items.iter().map(|item| item.bytes().map(text)).collect(),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    raw: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(raw: &'a [u8]) -> Self {
+        Parser { raw, pos: 0 }
+    }
+
+    /// Parses the top-level dictionary, also returning where its `info` value starts and ends.
+    fn document(mut self) -> io::Result<(Value<'a>, Option<(usize, usize)>)> {
+        if self.peek()? != b'd' {
+            return Err(self.error("not a dictionary"));
+        }
+        self.pos += 1;
+        let mut entries = BTreeMap::new();
+        let mut info = None;
+        while self.peek()? != b'e' {
+            let key = self.string()?;
+            let start = self.pos;
+            let value = self.value(1)?;
+            if key == b"info" {
+                info = Some((start, self.pos));
+            }
+            entries.insert(key, value);
+        }
+        self.pos += 1;
+        if self.pos != self.raw.len() {
+            return Err(self.error("trailing data"));
+        }
+        Ok((Value::Dict(entries), info))
+    }
+
+    fn value(&mut self, depth: usize) -> io::Result<Value<'a>> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        match self.peek()? {
+            b'i' => self.int().map(Value::Int),
+            b'l' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                while self.peek()? != b'e' {
+                    items.push(self.value(depth + 1)?);
+                }
+                self.pos += 1;
+                Ok(Value::List(items))
+            }
+            b'd' => {
+                self.pos += 1;
+                let mut entries = BTreeMap::new();
+                while self.peek()? != b'e' {
+                    let key = self.string()?;
+                    entries.insert(key, self.value(depth + 1)?);
+                }
+                self.pos += 1;
+                Ok(Value::Dict(entries))
+            }
+            b'0'..=b'9' => self.string().map(Value::Bytes),
+            _ => Err(self.error("unexpected byte")),
+        }
+    }
+
+    fn int(&mut self) -> io::Result<i64> {
+        self.pos += 1;
+        let digits = self.until(b'e')?;
+        let canonical = match digits {
+            [b'-', b'0', ..] | [] => false,
+            [b'0', rest @ ..] => rest.is_empty(),
+            _ => true,
+        };
+        std::str::from_utf8(digits)
+            .ok()
+            .filter(|_| canonical)
+            .and_then(|digits| digits.parse().ok())
+            .ok_or_else(|| self.error("malformed integer"))
+    }
+
+    fn string(&mut self) -> io::Result<&'a [u8]> {
+        if !self.peek()?.is_ascii_digit() {
+            return Err(self.error("expected a string"));
+        }
+        let length = std::str::from_utf8(self.until(b':')?)
+            .ok()
+            .and_then(|length| length.parse::<usize>().ok())
+            .ok_or_else(|| self.error("malformed string length"))?;
+        let end = self
+            .pos
+            .checked_add(length)
+            .filter(|&end| end <= self.raw.len())
+            .ok_or_else(|| self.error("truncated string"))?;
+        let string = &self.raw[self.pos..end];
+        self.pos = end;
+        Ok(string)
+    }
+
+    /// Returns the bytes up to `delimiter`, and moves past it.
+    fn until(&mut self, delimiter: u8) -> io::Result<&'a [u8]> {
+        let length = self.raw[self.pos..]
+            .iter()
+            .position(|&byte| byte == delimiter)
+            .ok_or_else(|| self.error("unexpected end"))?;
+        let bytes = &self.raw[self.pos..self.pos + length];
+        self.pos += length + 1;
+        Ok(bytes)
+    }
+
+    fn peek(&self) -> io::Result<u8> {
+        self.raw
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| self.error("unexpected end"))
+    }
+
+    fn error(&self, reason: &str) -> io::Error {
+        invalid(&format!("{reason} at byte {}", self.pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{Metainfo, MetainfoFile};
+    use crate::types::{Id, TorrentAddArgs};
+
+    const SINGLE: &[u8] = b"d8:announce31:http://tracker.example/announce7:comment4:test4:infod6:lengthi1024e4:name8:file.bin12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaa7:privatei1eee";
+
+    const MULTI: &[u8] = b"d13:announce-listll9:http://a/el9:http://b/9:http://c/ee4:infod5:filesld6:lengthi3e4:pathl3:dir5:a.txteed4:attr1:p6:lengthi5e4:pathl4:.pad1:5eed6:lengthi4e4:pathl5:b.txteee4:name4:root12:piece lengthi16384e6:pieces20:bbbbbbbbbbbbbbbbbbbbe8:url-list15:http://seed/dl/e";
+
+    const V2: &[u8] = b"d4:infod9:file treed5:b.txtd0:d6:lengthi2eee3:subd5:a.txtd0:d6:lengthi7eeeee12:meta versioni2e4:name4:tree12:piece lengthi16384eee";
+
+    #[test]
+    fn single_file() -> io::Result<()> {
+        let metainfo = Metainfo::from_bytes(SINGLE)?;
+        assert_eq!(metainfo.name, "file.bin");
+        assert_eq!(
+            metainfo.info_hash.as_deref(),
+            Some("897666df31d9b4d979cc754ac809d736c03ad195")
+        );
+        assert_eq!(metainfo.info_hash_v2, None);
+        assert_eq!(
+            metainfo.files,
+            vec![MetainfoFile {
+                name: "file.bin".to_string(),
+                length: 1024
+            }]
+        );
+        assert_eq!(metainfo.total_size, 1024);
+        assert!(metainfo.private);
+        assert_eq!(
+            metainfo.trackers,
+            vec![vec!["http://tracker.example/announce".to_string()]]
+        );
+        assert_eq!(metainfo.comment.as_deref(), Some("test"));
+        assert!(matches!(metainfo.id(), Id::Hash(hash) if hash == metainfo.hash_string()));
+        assert_eq!(
+            TorrentAddArgs::from(&metainfo).metainfo,
+            TorrentAddArgs::from_bytes(SINGLE).metainfo
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn multi_file_skips_padding() -> io::Result<()> {
+        let metainfo = Metainfo::from_bytes(MULTI)?;
+        let names: Vec<_> = metainfo.files.iter().map(|file| &file.name[..]).collect();
+        assert_eq!(names, ["root/dir/a.txt", "root/b.txt"]);
+        assert_eq!(metainfo.total_size, 7);
+        assert_eq!(metainfo.trackers.len(), 2);
+        assert_eq!(metainfo.trackers[1].len(), 2);
+        assert_eq!(metainfo.web_seeds, ["http://seed/dl/"]);
+        assert!(!metainfo.private);
+        Ok(())
+    }
+
+    #[test]
+    fn v2_file_tree() -> io::Result<()> {
+        let metainfo = Metainfo::from_bytes(V2)?;
+        assert_eq!(metainfo.info_hash, None);
+        assert_eq!(
+            metainfo.info_hash_v2.as_deref(),
+            Some("5570d73e005c78b5510a873a6353b5270a9e0fd2862bb9dde7c21f53e5bdcbbd")
+        );
+        assert_eq!(metainfo.hash_string().len(), 64);
+        let names: Vec<_> = metainfo.files.iter().map(|file| &file.name[..]).collect();
+        assert_eq!(names, ["tree/b.txt", "tree/sub/a.txt"]);
+        assert_eq!(metainfo.total_size, 9);
+        Ok(())
+    }
+
+    #[test]
+    fn malformed() {
+        for raw in [
+            &b""[..],
+            b"le",
+            b"d4:infoi-0ee",
+            b"d4:infoi03ee",
+            b"d4:info10:short",
+            b"d4:infodee",
+            b"d4:infod4:name1:x12:piece lengthi1eee",
+            &SINGLE[..SINGLE.len() - 1],
+            &[SINGLE, b"x"].concat(),
+        ] {
+            let error = Metainfo::from_bytes(raw).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{raw:?}");
+        }
+    }
+}
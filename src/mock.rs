@@ -0,0 +1,1132 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+//! An in-process Transmission daemon for hermetic tests.
+//!
+//! [`MockServer`] listens on a local port and speaks the legacy RPC protocol over HTTP/1.1,
+//! including the `X-Transmission-Session-Id` handshake and Basic Auth. It keeps torrents and
+//! session settings in memory and implements `torrent-add`, `torrent-get` (object and table
+//! formats, `"recently-active"` ids), `torrent-set`, `torrent-remove`, `torrent-start`,
+//! `torrent-start-now`, `torrent-stop`, `torrent-verify`, `torrent-reannounce`,
+//! `torrent-set-location`, `torrent-rename-path`, `session-get`, `session-set`,
+//! `session-stats`, `session-close`, the four queue moves and `free-space`. Torrents never
+//! download anything; their fields only change through RPC calls or
+//! [`MockServer::set_torrent_field`].
+//!
+//! # Example
+//!
+//! ```
+//! use transmission_rpc::{mock::MockServer, types::TorrentAddArgs};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> transmission_rpc::Result<()> {
+//! let server = MockServer::start().await.expect("bind a local port");
+//! let client = server.client();
+//!
+//! let add = TorrentAddArgs {
+//!     filename: Some("magnet:?xt=urn:btih:e08c426aab2cc58649ae5e73690e3747117b3470&dn=debian".into()),
+//!     ..TorrentAddArgs::default()
+//! };
+//! client.torrent_add(add).await?;
+//!
+//! let res = client.torrent_get(None, None).await?;
+//! assert_eq!(res.arguments.torrents[0].name.as_deref(), Some("debian"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::STANDARD as base64, Engine as _};
+use reqwest::Url;
+use serde_json::{json, Map, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+use crate::{
+    magnet::{self, MagnetLink},
+    metainfo::Metainfo,
+    types::BasicAuth,
+    TransClient,
+};
+
+/// How long a changed or removed torrent is reported by `"recently-active"` torrent-gets.
+const RECENTLY_ACTIVE: Duration = Duration::from_secs(60);
+
+/// Free space reported by `free-space`, 100 GiB.
+const FREE_SPACE: i64 = 100 * 1024 * 1024 * 1024;
+
+/// Torrent fields known to the mock, every torrent has all of them.
+const TORRENT_FIELDS: &[&str] = &[
+    "activityDate",
+    "addedDate",
+    "bandwidthPriority",
+    "doneDate",
+    "downloadDir",
+    "downloadLimit",
+    "downloadLimited",
+    "error",
+    "errorString",
+    "eta",
+    "file-count",
+    "files",
+    "fileStats",
+    "group",
+    "hashString",
+    "honorsSessionLimits",
+    "id",
+    "isFinished",
+    "isStalled",
+    "labels",
+    "leftUntilDone",
+    "magnetLink",
+    "name",
+    "peer-limit",
+    "peers",
+    "percentDone",
+    "priorities",
+    "queuePosition",
+    "rateDownload",
+    "rateUpload",
+    "recheckProgress",
+    "seedRatioLimit",
+    "seedRatioMode",
+    "sizeWhenDone",
+    "status",
+    "totalSize",
+    "trackers",
+    "trackerStats",
+    "uploadedEver",
+    "uploadLimit",
+    "uploadLimited",
+    "uploadRatio",
+    "wanted",
+];
+
+/// `torrent-set` arguments stored as-is, under the same name, in the torrent fields.
+const TORRENT_SET_FIELDS: &[&str] = &[
+    "bandwidthPriority",
+    "downloadLimit",
+    "downloadLimited",
+    "group",
+    "honorsSessionLimits",
+    "labels",
+    "peer-limit",
+    "seedRatioLimit",
+    "seedRatioMode",
+    "uploadLimit",
+    "uploadLimited",
+];
+
+/// A local, in-memory Transmission daemon, see the [module documentation](self).
+///
+/// The server stops when dropped. It must be started from within a Tokio runtime.
+pub struct MockServer {
+    url: Url,
+    auth: Option<BasicAuth>,
+    state: Arc<Mutex<State>>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Starts a server accepting any request, without authentication.
+    ///
+    /// # Errors
+    ///
+    /// If no local port can be bound
+    pub async fn start() -> io::Result<MockServer> {
+        MockServer::start_with(None).await
+    }
+
+    /// Starts a server answering 401 to requests without the given credentials.
+    ///
+    /// # Errors
+    ///
+    /// If no local port can be bound
+    pub async fn start_with_auth(auth: BasicAuth) -> io::Result<MockServer> {
+        MockServer::start_with(Some(auth)).await
+    }
+
+    async fn start_with(auth: Option<BasicAuth>) -> io::Result<MockServer> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::new(auth.as_ref())));
+        let task = tokio::spawn(serve(listener, state.clone()));
+        Ok(MockServer {
+            url: rpc_url(addr),
+            auth,
+            state,
+            task,
+        })
+    }
+
+    /// The RPC endpoint of this server.
+    #[must_use]
+    pub fn url(&self) -> Url {
+        self.url.clone()
+    }
+
+    /// A client for this server, with its credentials if any.
+    #[must_use]
+    pub fn client(&self) -> TransClient {
+        match &self.auth {
+            Some(auth) => TransClient::with_auth(self.url(), auth.clone()),
+            None => TransClient::new(self.url()),
+        }
+    }
+
+    /// The `X-Transmission-Session-Id` currently expected by the server.
+    #[must_use]
+    pub fn session_id(&self) -> String {
+        self.state().session_id.clone()
+    }
+
+    /// Changes the session id, so that the next request of every client is answered with 409.
+    pub fn renew_session_id(&self) {
+        self.state().renew_session_id();
+    }
+
+    /// Number of RPC requests handled so far, not counting 401 and 409 answers.
+    #[must_use]
+    pub fn request_count(&self) -> usize {
+        self.state().requests
+    }
+
+    /// Overwrites the `field` of the torrent with the given `id`, e.g. to simulate download
+    /// progress or an error. Returns `false` if there is no such torrent.
+    pub fn set_torrent_field(&self, id: i64, field: &str, value: Value) -> bool {
+        let mut state = self.state();
+        let Some(torrent) = state.torrents.iter_mut().find(|t| t.id() == id) else {
+            return false;
+        };
+        torrent.set(field, value);
+        true
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("mock state poisoned")
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn rpc_url(addr: SocketAddr) -> Url {
+    format!("http://{addr}/transmission/rpc")
+        .parse()
+        .expect("valid url")
+}
+
+async fn serve(listener: TcpListener, state: Arc<Mutex<State>>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(connection(stream, state.clone()));
+    }
+}
+
+async fn connection(stream: TcpStream, state: Arc<Mutex<State>>) -> io::Result<()> {
+    let mut stream = BufReader::new(stream);
+    while let Some(request) = read_request(&mut stream).await? {
+        let response = state.lock().expect("mock state poisoned").handle(&request);
+        stream.get_mut().write_all(&response.into_bytes()).await?;
+    }
+    Ok(())
+}
+
+struct HttpRequest {
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Reads one request, or `None` once the client closed the connection.
+async fn read_request(stream: &mut BufReader<TcpStream>) -> io::Result<Option<HttpRequest>> {
+    let mut line = String::new();
+    if stream.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        stream.read_line(&mut line).await?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    let mut request = HttpRequest {
+        headers,
+        body: Vec::new(),
+    };
+    let length = request
+        .header("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    request.body.resize(length, 0);
+    stream.read_exact(&mut request.body).await?;
+    Ok(Some(request))
+}
+
+struct HttpResponse {
+    status: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: String,
+}
+
+impl HttpResponse {
+    fn new(status: &'static str, body: String) -> Self {
+        HttpResponse {
+            status,
+            headers: Vec::new(),
+            body,
+        }
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        let mut out = format!(
+            "HTTP/1.1 {}\r\ncontent-length: {}\r\n",
+            self.status,
+            self.body.len()
+        );
+        for (name, value) in self.headers {
+            out.push_str(&format!("{name}: {value}\r\n"));
+        }
+        out.push_str("\r\n");
+        out.push_str(&self.body);
+        out.into_bytes()
+    }
+}
+
+struct MockTorrent {
+    fields: Map<String, Value>,
+    changed: Instant,
+}
+
+impl MockTorrent {
+    fn id(&self) -> i64 {
+        self.fields["id"].as_i64().unwrap_or_default()
+    }
+
+    fn get(&self, field: &str) -> &Value {
+        self.fields.get(field).unwrap_or(&Value::Null)
+    }
+
+    fn set(&mut self, field: &str, value: Value) {
+        self.fields.insert(field.to_string(), value);
+        self.changed = Instant::now();
+    }
+
+    /// Applies the arguments of a `torrent-set`, ignoring the ones the mock does not model.
+    fn update(&mut self, args: &Map<String, Value>) {
+        for (key, value) in args {
+            match key.as_str() {
+                "location" => self.set("downloadDir", value.clone()),
+                "files-wanted" => self.set_files("wanted", value, json!(true)),
+                "files-unwanted" => self.set_files("wanted", value, json!(false)),
+                "priority-high" => self.set_files("priorities", value, json!(1)),
+                "priority-normal" => self.set_files("priorities", value, json!(0)),
+                "priority-low" => self.set_files("priorities", value, json!(-1)),
+                key if TORRENT_SET_FIELDS.contains(&key) => self.set(key, value.clone()),
+                _ => {}
+            }
+        }
+    }
+
+    /// Sets the entries of the per-file `field` at the indices listed in `indices`.
+    fn set_files(&mut self, field: &str, indices: &Value, value: Value) {
+        let mut entries = self.get(field).clone();
+        if let Value::Array(entries) = &mut entries {
+            for index in indices
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_u64)
+            {
+                if let Some(entry) = entries.get_mut(index as usize) {
+                    *entry = value.clone();
+                }
+            }
+        }
+        self.set(field, entries);
+    }
+
+    fn matches(&self, id: &Value) -> bool {
+        match id {
+            Value::Number(id) => id.as_i64() == Some(self.id()),
+            Value::String(hash) => self.get("hashString").as_str() == Some(hash),
+            _ => false,
+        }
+    }
+
+    fn recently_active(&self) -> bool {
+        self.changed.elapsed() < RECENTLY_ACTIVE
+    }
+
+    fn project(&self, fields: &[String]) -> Map<String, Value> {
+        fields
+            .iter()
+            .filter_map(|field| Some((field.clone(), self.fields.get(field)?.clone())))
+            .collect()
+    }
+}
+
+struct State {
+    authorization: Option<String>,
+    session_id: String,
+    sessions: u64,
+    session: Map<String, Value>,
+    torrents: Vec<MockTorrent>,
+    removed: Vec<(i64, Instant)>,
+    next_id: i64,
+    files_added: i64,
+    requests: usize,
+    started: Instant,
+}
+
+impl State {
+    fn new(auth: Option<&BasicAuth>) -> Self {
+        let authorization = auth.map(|auth| {
+            format!(
+                "Basic {}",
+                base64.encode(format!("{}:{}", auth.user, auth.password))
+            )
+        });
+        let Value::Object(session) = json!({
+            "alt-speed-down": 50,
+            "alt-speed-enabled": false,
+            "alt-speed-up": 50,
+            "blocklist-enabled": false,
+            "blocklist-size": 0,
+            "cache-size-mb": 4,
+            "config-dir": "/config",
+            "dht-enabled": true,
+            "download-dir": "/downloads/complete",
+            "download-queue-enabled": true,
+            "download-queue-size": 5,
+            "encryption": "preferred",
+            "idle-seeding-limit": 30,
+            "idle-seeding-limit-enabled": false,
+            "incomplete-dir": "/downloads/incomplete",
+            "incomplete-dir-enabled": false,
+            "lpd-enabled": false,
+            "peer-limit-global": 200,
+            "peer-limit-per-torrent": 50,
+            "peer-port": 51413,
+            "pex-enabled": true,
+            "port-forwarding-enabled": false,
+            "queue-stalled-enabled": true,
+            "queue-stalled-minutes": 30,
+            "rename-partial-files": true,
+            "rpc-version": 17,
+            "rpc-version-minimum": 14,
+            "rpc-version-semver": "5.3.0",
+            "seed-queue-enabled": false,
+            "seed-queue-size": 10,
+            "seedRatioLimit": 2.0,
+            "seedRatioLimited": false,
+            "speed-limit-down": 100,
+            "speed-limit-down-enabled": false,
+            "speed-limit-up": 100,
+            "speed-limit-up-enabled": false,
+            "start-added-torrents": true,
+            "trash-original-torrent-files": false,
+            "utp-enabled": true,
+            "version": "4.0.6 (mock)",
+        }) else {
+            unreachable!("session is an object");
+        };
+        let mut state = State {
+            authorization,
+            session_id: String::new(),
+            sessions: 0,
+            session,
+            torrents: Vec::new(),
+            removed: Vec::new(),
+            next_id: 1,
+            files_added: 0,
+            requests: 0,
+            started: Instant::now(),
+        };
+        state.renew_session_id();
+        state
+    }
+
+    fn renew_session_id(&mut self) {
+        self.sessions += 1;
+        self.session_id = format!("mock-session-{}", self.sessions);
+    }
+
+    fn handle(&mut self, request: &HttpRequest) -> HttpResponse {
+        if let Some(authorization) = &self.authorization {
+            if request.header("authorization") != Some(authorization) {
+                let mut response = HttpResponse::new("401 Unauthorized", "Unauthorized".into());
+                response
+                    .headers
+                    .push(("www-authenticate", "Basic realm=\"Transmission\"".into()));
+                return response;
+            }
+        }
+        if request.header("x-transmission-session-id") != Some(&self.session_id) {
+            let mut response = HttpResponse::new("409 Conflict", "Invalid session id".into());
+            response
+                .headers
+                .push(("x-transmission-session-id", self.session_id.clone()));
+            return response;
+        }
+        let Ok(Value::Object(mut rpc)) = serde_json::from_slice(&request.body) else {
+            return HttpResponse::new("400 Bad Request", "Invalid JSON".into());
+        };
+        self.requests += 1;
+        let method = rpc
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let args = match rpc.remove("arguments") {
+            Some(Value::Object(args)) => args,
+            _ => Map::new(),
+        };
+        let (result, arguments) = match self.call(&method, &args) {
+            Ok(arguments) => ("success".to_string(), arguments),
+            Err(result) => (result, json!({})),
+        };
+        let mut body = json!({ "arguments": arguments, "result": result });
+        if let Some(tag) = rpc.remove("tag") {
+            body["tag"] = tag;
+        }
+        let mut response = HttpResponse::new("200 OK", body.to_string());
+        response
+            .headers
+            .push(("content-type", "application/json".into()));
+        response
+    }
+
+    fn call(&mut self, method: &str, args: &Map<String, Value>) -> Result<Value, String> {
+        match method {
+            "session-get" => Ok(self.session_get(args)),
+            "session-set" => {
+                for (key, value) in args {
+                    self.session.insert(key.clone(), value.clone());
+                }
+                Ok(json!({}))
+            }
+            "session-stats" => Ok(self.session_stats()),
+            "session-close" => Ok(json!({})),
+            "free-space" => {
+                let path = args.get("path").cloned().unwrap_or(Value::Null);
+                Ok(json!({ "path": path, "size-bytes": FREE_SPACE, "total_size": FREE_SPACE }))
+            }
+            "torrent-add" => self.torrent_add(args),
+            "torrent-get" => Ok(self.torrent_get(args)),
+            "torrent-set" => {
+                for i in self.select(args) {
+                    self.torrents[i].update(args);
+                }
+                Ok(json!({}))
+            }
+            "torrent-remove" => {
+                let selected = self.select(args);
+                let now = Instant::now();
+                for i in selected.into_iter().rev() {
+                    let torrent = self.torrents.remove(i);
+                    self.removed.push((torrent.id(), now));
+                }
+                self.requeue(|order| order);
+                Ok(json!({}))
+            }
+            "torrent-start" | "torrent-start-now" => {
+                for i in self.select(args) {
+                    let torrent = &mut self.torrents[i];
+                    let finished = torrent.get("percentDone").as_f64() >= Some(1.0);
+                    torrent.set("status", (if finished { 6 } else { 4 }).into());
+                }
+                Ok(json!({}))
+            }
+            "torrent-stop" => {
+                for i in self.select(args) {
+                    self.torrents[i].set("status", 0.into());
+                }
+                Ok(json!({}))
+            }
+            "torrent-verify" | "torrent-reannounce" => Ok(json!({})),
+            "torrent-set-location" => {
+                let location = args.get("location").cloned().unwrap_or(Value::Null);
+                for i in self.select(args) {
+                    self.torrents[i].set("downloadDir", location.clone());
+                }
+                Ok(json!({}))
+            }
+            "torrent-rename-path" => self.torrent_rename_path(args),
+            "queue-move-top" => Ok(self.queue_move(args, |selected, others| {
+                selected.into_iter().chain(others).collect()
+            })),
+            "queue-move-bottom" => Ok(self.queue_move(args, |selected, others| {
+                others.into_iter().chain(selected).collect()
+            })),
+            "queue-move-up" | "queue-move-down" => {
+                let selected = self.select_ids(args);
+                let up = method == "queue-move-up";
+                self.requeue(|mut order| {
+                    let swaps: Vec<usize> = if up {
+                        (1..order.len()).collect()
+                    } else {
+                        (0..order.len().saturating_sub(1)).rev().collect()
+                    };
+                    for i in swaps {
+                        let other = if up { i - 1 } else { i + 1 };
+                        if selected.contains(&order[i]) && !selected.contains(&order[other]) {
+                            order.swap(i, other);
+                        }
+                    }
+                    order
+                });
+                Ok(json!({}))
+            }
+            _ => Err("method name not recognized".into()),
+        }
+    }
+
+    fn session_get(&self, args: &Map<String, Value>) -> Value {
+        let mut session = self.session.clone();
+        session.insert("session-id".into(), self.session_id.clone().into());
+        if let Some(Value::Array(fields)) = args.get("fields") {
+            session.retain(|key, _| fields.iter().any(|field| field.as_str() == Some(key)));
+        }
+        Value::Object(session)
+    }
+
+    fn session_stats(&self) -> Value {
+        let paused = self
+            .torrents
+            .iter()
+            .filter(|torrent| torrent.get("status").as_i64() == Some(0))
+            .count();
+        let stats = json!({
+            "filesAdded": self.files_added,
+            "downloadedBytes": 0,
+            "uploadedBytes": 0,
+            "secondsActive": self.started.elapsed().as_secs(),
+            "sessionCount": 1,
+        });
+        json!({
+            "torrentCount": self.torrents.len(),
+            "activeTorrentCount": self.torrents.len() - paused,
+            "pausedTorrentCount": paused,
+            "downloadSpeed": 0,
+            "uploadSpeed": 0,
+            "current-stats": stats,
+            "cumulative-stats": stats,
+        })
+    }
+
+    fn torrent_add(&mut self, args: &Map<String, Value>) -> Result<Value, String> {
+        let (name, hash, magnet) = match (args.get("filename"), args.get("metainfo")) {
+            (_, Some(Value::String(metainfo))) => {
+                let bytes = base64
+                    .decode(metainfo)
+                    .map_err(|_| "invalid or corrupt torrent file".to_string())?;
+                match Metainfo::from_bytes(bytes.as_slice()) {
+                    Ok(metainfo) => (
+                        Some(metainfo.name.clone()),
+                        metainfo.hash_string().to_string(),
+                        None,
+                    ),
+                    Err(_) => (None, fake_hash(&bytes), None),
+                }
+            }
+            (Some(Value::String(filename)), _) if filename.starts_with("magnet:") => {
+                let link = filename
+                    .parse::<MagnetLink>()
+                    .map_err(|_| "invalid or corrupt torrent file".to_string())?;
+                let hash = link.hash_string().to_string();
+                (link.display_name, hash, Some(filename.clone()))
+            }
+            (Some(Value::String(filename)), _) => {
+                let name = filename
+                    .rsplit('/')
+                    .next()
+                    .map(|name| name.trim_end_matches(".torrent").to_string());
+                (name, fake_hash(filename.as_bytes()), None)
+            }
+            _ => return Err("no filename or metainfo specified".into()),
+        };
+        if let Some(torrent) = self
+            .torrents
+            .iter()
+            .find(|torrent| torrent.get("hashString").as_str() == Some(&hash))
+        {
+            let fields = ["id", "name", "hashString"].map(String::from);
+            return Ok(json!({ "torrent-duplicate": torrent.project(&fields) }));
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.files_added += 1;
+        let name = name.unwrap_or_else(|| format!("torrent-{id}"));
+        let paused = args
+            .get("paused")
+            .and_then(Value::as_bool)
+            .unwrap_or_else(|| self.session["start-added-torrents"] != Value::Bool(true));
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let magnet = magnet
+            .unwrap_or_else(|| format!("magnet:?xt=urn:btih:{hash}&dn={}", magnet::encode(&name)));
+        let arg = |key, default| args.get(key).cloned().unwrap_or(default);
+        let download_dir = arg("download-dir", self.session["download-dir"].clone());
+        let fields = [
+            ("activityDate", json!(now)),
+            ("addedDate", json!(now)),
+            ("bandwidthPriority", arg("bandwidthPriority", json!(0))),
+            ("doneDate", json!(0)),
+            ("downloadDir", download_dir),
+            ("downloadLimit", json!(100)),
+            ("downloadLimited", json!(false)),
+            ("error", json!(0)),
+            ("errorString", json!("")),
+            ("eta", json!(-1)),
+            ("file-count", json!(0)),
+            ("files", json!([])),
+            ("fileStats", json!([])),
+            ("group", json!("")),
+            ("hashString", json!(hash)),
+            ("honorsSessionLimits", json!(true)),
+            ("id", json!(id)),
+            ("isFinished", json!(false)),
+            ("isStalled", json!(false)),
+            ("labels", arg("labels", json!([]))),
+            ("leftUntilDone", json!(0)),
+            ("magnetLink", json!(magnet)),
+            ("name", json!(name)),
+            ("peer-limit", arg("peer-limit", json!(50))),
+            ("peers", json!([])),
+            ("percentDone", json!(0.0)),
+            ("priorities", json!([])),
+            ("queuePosition", json!(self.torrents.len())),
+            ("rateDownload", json!(0)),
+            ("rateUpload", json!(0)),
+            ("recheckProgress", json!(0.0)),
+            ("seedRatioLimit", json!(2.0)),
+            ("seedRatioMode", json!(0)),
+            ("sizeWhenDone", json!(0)),
+            ("status", json!(if paused { 0 } else { 4 })),
+            ("totalSize", json!(0)),
+            ("trackers", json!([])),
+            ("trackerStats", json!([])),
+            ("uploadedEver", json!(0)),
+            ("uploadLimit", json!(100)),
+            ("uploadLimited", json!(false)),
+            ("uploadRatio", json!(0.0)),
+            ("wanted", json!([])),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+        let torrent = MockTorrent {
+            fields,
+            changed: Instant::now(),
+        };
+        let added = torrent.project(&["id", "name", "hashString"].map(String::from));
+        self.torrents.push(torrent);
+        Ok(json!({ "torrent-added": added }))
+    }
+
+    fn torrent_get(&mut self, args: &Map<String, Value>) -> Value {
+        let fields: Vec<String> = match args.get("fields") {
+            Some(Value::Array(fields)) => fields
+                .iter()
+                .filter_map(Value::as_str)
+                .filter(|field| TORRENT_FIELDS.contains(field))
+                .map(String::from)
+                .collect(),
+            _ => Vec::new(),
+        };
+        let selected = self.select(args);
+        let torrents = selected.into_iter().map(|i| &self.torrents[i]);
+        let torrents = if args.get("format").and_then(Value::as_str) == Some("table") {
+            let header = Value::from(fields.clone());
+            let rows = torrents.map(|torrent| {
+                fields
+                    .iter()
+                    .map(|field| torrent.get(field).clone())
+                    .collect()
+            });
+            std::iter::once(header).chain(rows).collect()
+        } else {
+            torrents
+                .map(|torrent| Value::Object(torrent.project(&fields)))
+                .collect::<Vec<_>>()
+        };
+        let mut arguments = json!({ "torrents": torrents });
+        if args.get("ids").and_then(Value::as_str) == Some("recently-active") {
+            self.removed
+                .retain(|(_, removed)| removed.elapsed() < RECENTLY_ACTIVE);
+            let removed: Vec<i64> = self.removed.iter().map(|(id, _)| *id).collect();
+            arguments["removed"] = removed.into();
+        }
+        arguments
+    }
+
+    fn torrent_rename_path(&mut self, args: &Map<String, Value>) -> Result<Value, String> {
+        let selected = self.select(args);
+        let [i] = selected[..] else {
+            return Err("torrent-rename-path requires 1 torrent".into());
+        };
+        let path = args.get("path").cloned().unwrap_or(Value::Null);
+        let name = args.get("name").cloned().unwrap_or(Value::Null);
+        let torrent = &mut self.torrents[i];
+        if torrent.get("name") == &path {
+            torrent.set("name", name.clone());
+        }
+        Ok(json!({ "id": torrent.id(), "path": path, "name": name }))
+    }
+
+    /// Returns the indices of the torrents selected by the `ids` argument.
+    fn select(&self, args: &Map<String, Value>) -> Vec<usize> {
+        let selected = |torrent: &MockTorrent| match args.get("ids") {
+            None => true,
+            Some(Value::String(ids)) if ids == "recently-active" => torrent.recently_active(),
+            Some(Value::Array(ids)) => ids.iter().any(|id| torrent.matches(id)),
+            Some(id) => torrent.matches(id),
+        };
+        (0..self.torrents.len())
+            .filter(|&i| selected(&self.torrents[i]))
+            .collect()
+    }
+
+    fn select_ids(&self, args: &Map<String, Value>) -> Vec<i64> {
+        self.select(args)
+            .into_iter()
+            .map(|i| self.torrents[i].id())
+            .collect()
+    }
+
+    fn queue_move(
+        &mut self,
+        args: &Map<String, Value>,
+        reorder: impl FnOnce(Vec<i64>, Vec<i64>) -> Vec<i64>,
+    ) -> Value {
+        let selected = self.select_ids(args);
+        self.requeue(|order| {
+            let (selected, others) = order.into_iter().partition(|id| selected.contains(id));
+            reorder(selected, others)
+        });
+        json!({})
+    }
+
+    /// Reorders the queue with `reorder`, which gets the torrent ids by queue position, and
+    /// renumbers the queue positions.
+    fn requeue(&mut self, reorder: impl FnOnce(Vec<i64>) -> Vec<i64>) {
+        let mut order: Vec<(i64, i64)> = self
+            .torrents
+            .iter()
+            .map(|torrent| {
+                let position = torrent.get("queuePosition").as_i64().unwrap_or_default();
+                (position, torrent.id())
+            })
+            .collect();
+        order.sort_unstable();
+        let order = reorder(order.into_iter().map(|(_, id)| id).collect());
+        for (position, id) in order.into_iter().enumerate() {
+            if let Some(torrent) = self.torrents.iter_mut().find(|t| t.id() == id) {
+                if torrent.get("queuePosition").as_u64() != Some(position as u64) {
+                    torrent.set("queuePosition", position.into());
+                }
+            }
+        }
+    }
+}
+
+/// Returns a stable 40 hex digit stand-in for the SHA-1 info hash of `data`.
+fn fake_hash(data: &[u8]) -> String {
+    let part = |seed: u8| {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        data.hash(&mut hasher);
+        hasher.finish()
+    };
+    format!("{:016x}{:016x}{:08x}", part(0), part(1), part(2) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{Metainfo, MockServer};
+    use crate::{
+        magnet::MagnetLink,
+        types::{
+            BasicAuth, Id, Ids, Priority, Result, SessionSetArgs, TorrentAction, TorrentAddArgs,
+            TorrentAddedOrDuplicate, TorrentGetField, TorrentSetArgs, TorrentStatus,
+        },
+        Error,
+    };
+
+    const MAGNET: &str =
+        "magnet:?xt=urn:btih:E08C426AAB2CC58649AE5E73690E3747117B3470&dn=debian.iso";
+
+    fn magnet(hash: char) -> TorrentAddArgs {
+        TorrentAddArgs {
+            filename: Some(format!(
+                "magnet:?xt=urn:btih:{}",
+                hash.to_string().repeat(40)
+            )),
+            ..TorrentAddArgs::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn session_id_handshake() -> Result<()> {
+        let server = MockServer::start().await?;
+        let client = server.client();
+        client.session_stats().await?;
+        server.renew_session_id();
+        client.session_stats().await?;
+        assert_eq!(server.request_count(), 2);
+        assert_eq!(server.session_id(), "mock-session-2");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn basic_auth() -> Result<()> {
+        let auth = BasicAuth {
+            user: "user".into(),
+            password: "secret".into(),
+        };
+        let server = MockServer::start_with_auth(auth).await?;
+        assert!(server.client().session_get(None).await?.is_ok());
+
+        let client = crate::TransClient::new(server.url());
+        assert!(matches!(
+            client.session_get(None).await,
+            Err(Error::Unauthorized)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn add_get_set_remove() -> Result<()> {
+        let server = MockServer::start().await?;
+        let client = server.client();
+        let add = TorrentAddArgs {
+            filename: Some(MAGNET.into()),
+            labels: Some(vec!["linux".into()]),
+            ..TorrentAddArgs::default()
+        };
+        let added = client.torrent_add(add.clone()).await?.arguments;
+        assert!(matches!(added, TorrentAddedOrDuplicate::TorrentAdded(t) if t.id == Some(1)));
+        let duplicate = client.torrent_add(add).await?.arguments;
+        assert!(matches!(
+            duplicate,
+            TorrentAddedOrDuplicate::TorrentDuplicate(_)
+        ));
+
+        let torrents = client.torrent_get(None, None).await?.arguments.torrents;
+        assert_eq!(torrents.len(), 1);
+        assert_eq!(torrents[0].name.as_deref(), Some("debian.iso"));
+        assert_eq!(
+            torrents[0].hash_string.as_deref(),
+            Some("e08c426aab2cc58649ae5e73690e3747117b3470")
+        );
+        assert_eq!(torrents[0].labels, Some(vec!["linux".to_string()]));
+
+        let args = TorrentSetArgs::new().labels(vec!["iso".into()]);
+        client.torrent_set(args, vec![Id::Id(1)]).await?;
+        client
+            .torrent_action(TorrentAction::Stop, vec![Id::Id(1)])
+            .await?;
+        let torrent = &client
+            .torrent_get(
+                Some(vec![TorrentGetField::Labels, TorrentGetField::Status]),
+                vec![Id::Hash("e08c426aab2cc58649ae5e73690e3747117b3470".into())],
+            )
+            .await?
+            .arguments
+            .torrents[0];
+        assert_eq!(torrent.labels, Some(vec!["iso".to_string()]));
+        assert_eq!(torrent.status, Some(TorrentStatus::Stopped));
+        assert_eq!(torrent.name, None);
+
+        client.torrent_remove(vec![Id::Id(1)], true).await?;
+        let response = client.torrent_get(None, Ids::RecentlyActive).await?;
+        assert!(response.arguments.torrents.is_empty());
+        assert_eq!(response.arguments.removed, Some(vec![1]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn table_format() -> Result<()> {
+        let server = MockServer::start().await?;
+        let client = server.client();
+        client.torrent_add(magnet('a')).await?;
+        client.torrent_add(magnet('b')).await?;
+        assert!(server.set_torrent_field(2, "percentDone", 0.5.into()));
+        let torrents = client
+            .torrent_get_table(
+                Some(vec![TorrentGetField::Id, TorrentGetField::PercentDone]),
+                None,
+            )
+            .await?
+            .arguments
+            .torrents;
+        assert_eq!(torrents.len(), 2);
+        assert_eq!(torrents[1].percent_done, Some(0.5));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn queue_moves() -> Result<()> {
+        let server = MockServer::start().await?;
+        let client = server.client();
+        for hash in ['a', 'b', 'c'] {
+            client.torrent_add(magnet(hash)).await?;
+        }
+        let queue = || {
+            let client = server.client();
+            async move {
+                let mut torrents = client
+                    .torrent_get(
+                        Some(vec![TorrentGetField::Id, TorrentGetField::QueuePosition]),
+                        None,
+                    )
+                    .await?
+                    .arguments
+                    .torrents;
+                torrents.sort_by_key(|t| t.queue_position);
+                Result::Ok(
+                    torrents
+                        .into_iter()
+                        .filter_map(|t| t.id)
+                        .collect::<Vec<_>>(),
+                )
+            }
+        };
+        client.queue_move_bottom(vec![Id::Id(1)]).await?;
+        assert_eq!(queue().await?, vec![2, 3, 1]);
+        client.queue_move_up(vec![Id::Id(1)]).await?;
+        assert_eq!(queue().await?, vec![2, 1, 3]);
+        client.queue_move_top(vec![Id::Id(3)]).await?;
+        assert_eq!(queue().await?, vec![3, 2, 1]);
+        client.queue_move_down(vec![Id::Id(3)]).await?;
+        assert_eq!(queue().await?, vec![2, 3, 1]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn session() -> Result<()> {
+        let server = MockServer::start().await?;
+        let client = server.client();
+        let args = SessionSetArgs {
+            download_dir: Some("/data".into()),
+            start_added_torrents: Some(false),
+            ..SessionSetArgs::default()
+        };
+        client.session_set(args).await?;
+        let session = client.session_get(None).await?.arguments;
+        assert_eq!(session.download_dir.as_deref(), Some("/data"));
+        assert_eq!(session.session_id, Some(server.session_id()));
+
+        client.torrent_add(magnet('a')).await?;
+        let stats = client.session_stats().await?.arguments;
+        assert_eq!(stats.torrent_count, 1);
+        assert_eq!(stats.paused_torrent_count, 1);
+
+        let free_space = client.free_space("/data".into()).await?.arguments;
+        assert_eq!(free_space.path, "/data");
+        assert!(free_space.size_bytes > 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn metainfo_name_and_hash() -> Result<()> {
+        let server = MockServer::start().await?;
+        let client = server.client();
+        let torrent = b"d4:infod6:lengthi1e4:name5:a.bin12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+        let metainfo = Metainfo::from_bytes(&torrent[..])?;
+        client.torrent_add(TorrentAddArgs::from(&metainfo)).await?;
+        let torrents = client
+            .torrent_get(None, vec![metainfo.id()])
+            .await?
+            .arguments
+            .torrents;
+        assert_eq!(torrents[0].name.as_deref(), Some("a.bin"));
+        assert_eq!(
+            torrents[0].hash_string.as_deref(),
+            Some(metainfo.hash_string())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn magnet_link_names_are_encoded() -> Result<()> {
+        let server = MockServer::start().await?;
+        let client = server.client();
+        client
+            .torrent_add(TorrentAddArgs {
+                filename: Some("/torrents/a b&c#d.torrent".into()),
+                ..TorrentAddArgs::default()
+            })
+            .await?;
+        let torrents = client
+            .torrent_get(Some(vec![TorrentGetField::MagnetLink]), None)
+            .await?
+            .arguments
+            .torrents;
+        let link: MagnetLink = torrents[0]
+            .magnet_link
+            .as_deref()
+            .unwrap_or_default()
+            .parse()?;
+        assert_eq!(link.display_name.as_deref(), Some("a b&c#d"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn torrent_set_updates_torrent_get_fields() -> Result<()> {
+        let server = MockServer::start().await?;
+        let client = server.client();
+        client.torrent_add(magnet('a')).await?;
+        server.set_torrent_field(1, "wanted", json!([true, true]));
+        server.set_torrent_field(1, "priorities", json!([0, 0]));
+
+        let args = TorrentSetArgs::new()
+            .location("/moved".into())
+            .files_unwanted(vec![1])
+            .priority_high(vec![0])
+            .tracker_add(vec!["http://tracker.example/announce".into()]);
+        client.torrent_set(args, vec![Id::Id(1)]).await?;
+        let torrent = &client.torrent_get(None, None).await?.arguments.torrents[0];
+        assert_eq!(torrent.download_dir.as_deref(), Some("/moved"));
+        assert_eq!(torrent.wanted, Some(vec![true, false]));
+        assert_eq!(
+            torrent.priorities,
+            Some(vec![Priority::High, Priority::Normal])
+        );
+        assert_eq!(torrent.trackers.as_deref().map(<[_]>::len), Some(0));
+        Ok(())
+    }
+}
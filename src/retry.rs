@@ -0,0 +1,301 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+use std::{
+    cell::Cell,
+    future::Future,
+    io::ErrorKind,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{trace, types::RpcRequest, Error, Result};
+
+/// Controls how failed calls are retried.
+///
+/// Session id renewals (HTTP 409) are part of the normal handshake and are not counted as
+/// attempts.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use transmission_rpc::RetryPolicy;
+///
+/// let policy = RetryPolicy {
+///     max_attempts: 5,
+///     max_backoff: Duration::from_secs(10),
+///     retry_non_idempotent: true,
+///     ..RetryPolicy::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one. `1` disables retries.
+    pub max_attempts: usize,
+    /// Delay before the first retry. Doubled for every following retry.
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between two attempts.
+    pub max_backoff: Duration,
+    /// Randomize each delay between half and all of its computed value.
+    pub jitter: bool,
+    /// Retry when the connection to the daemon could not be established or was reset.
+    pub retry_connection_errors: bool,
+    /// Retry when the request timed out. Only idempotent methods are retried then, even with
+    /// `retry_non_idempotent`, as the daemon may have processed the request.
+    pub retry_timeouts: bool,
+    /// Retry when the daemon (or a reverse proxy in front of it) answered with a 5xx status.
+    pub retry_server_errors: bool,
+    /// Also retry methods that are not safe to repeat, such as `torrent-add`,
+    /// `torrent-remove`, `torrent-set-location`, `torrent-rename-path`, relative queue moves
+    /// and `session-close`.
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    /// Up to 3 attempts of idempotent methods on connection errors, timeouts and 5xx, starting
+    /// with a 100ms backoff capped at 5s.
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            jitter: true,
+            retry_connection_errors: true,
+            retry_timeouts: true,
+            retry_server_errors: true,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    #[must_use]
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Returns whether `request` may be retried at all under this policy.
+    pub(crate) fn allows(&self, request: &RpcRequest) -> bool {
+        self.max_attempts > 1 && (self.retry_non_idempotent || request.is_idempotent())
+    }
+
+    /// Runs `attempt`, which sends `requests`, until it succeeds, fails for good, or this policy
+    /// gives up. Failures are only retried if every request is [allowed](Self::allows), and
+    /// timeouts only if every request is idempotent.
+    pub(crate) async fn run<T, F, Fut>(&self, requests: &[RpcRequest], mut attempt: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let retryable = requests.iter().all(|request| self.allows(request));
+        let idempotent = requests.iter().all(RpcRequest::is_idempotent);
+        let mut attempts = 1;
+        loop {
+            trace::attempt(attempts);
+            match attempt().await {
+                Err(e)
+                    if retryable
+                        && attempts < self.max_attempts
+                        && self.is_retryable(&e)
+                        && (idempotent || !is_timeout(&e)) =>
+                {
+                    let delay = self.backoff(attempts as u32);
+                    debug!("Attempt {attempts} failed: {e}. Retrying in {delay:?}.");
+                    tokio::time::sleep(delay).await;
+                    attempts += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Returns whether `error` is worth another attempt under this policy.
+    #[must_use]
+    pub fn is_retryable(&self, error: &Error) -> bool {
+        match error {
+            Error::Transport(e) if e.is_timeout() => self.retry_timeouts,
+            Error::Transport(e) if e.is_connect() => self.retry_connection_errors,
+            Error::Io(e) => match e.kind() {
+                ErrorKind::TimedOut => self.retry_timeouts,
+                ErrorKind::ConnectionRefused
+                | ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::NotConnected
+                | ErrorKind::BrokenPipe => self.retry_connection_errors,
+                _ => false,
+            },
+            Error::HttpStatus { status, .. } => {
+                status.is_server_error() && self.retry_server_errors
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the delay to wait before the `retry`-th retry (starting at `1`).
+    #[must_use]
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        let delay = self
+            .initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff);
+        if !self.jitter {
+            return delay;
+        }
+        let half = delay / 2;
+        half + half.mul_f64(random())
+    }
+}
+
+fn is_timeout(error: &Error) -> bool {
+    match error {
+        Error::Transport(e) => e.is_timeout(),
+        Error::Io(e) => e.kind() == ErrorKind::TimedOut,
+        _ => false,
+    }
+}
+
+/// Returns a number in `[0, 1)` to spread the retries of concurrent clients.
+///
+/// This is the xorshift64* generator, one per thread, seeded with the current time. It is only
+/// meant for jitter and must not be used where unpredictability matters.
+fn random() -> f64 {
+    thread_local! {
+        static STATE: Cell<u64> = Cell::new(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+                | 1,
+        );
+    }
+    STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        // The top 53 bits fill the mantissa of an f64 in [0, 1).
+        (x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1u64 << 53) as f64
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, time::Duration};
+
+    use reqwest::StatusCode;
+
+    use super::RetryPolicy;
+    use crate::{
+        types::{Id, RpcRequest, TorrentAddArgs},
+        Error,
+    };
+
+    #[test]
+    fn backoff_is_exponential_and_capped() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            jitter: false,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn backoff_jitter_stays_in_range() {
+        let policy = RetryPolicy::default();
+        for _ in 0..100 {
+            let delay = policy.backoff(2);
+            assert!(delay >= Duration::from_millis(100));
+            assert!(delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn server_errors_are_retryable() {
+        let policy = RetryPolicy::default();
+        let error = |status| Error::HttpStatus {
+            status,
+            body: String::new(),
+        };
+        assert!(policy.is_retryable(&error(StatusCode::BAD_GATEWAY)));
+        assert!(!policy.is_retryable(&error(StatusCode::NOT_FOUND)));
+        assert!(!policy.is_retryable(&Error::Unauthorized));
+
+        let policy = RetryPolicy {
+            retry_server_errors: false,
+            ..RetryPolicy::default()
+        };
+        assert!(!policy.is_retryable(&error(StatusCode::BAD_GATEWAY)));
+    }
+
+    #[test]
+    fn io_connection_errors_are_retryable() {
+        let policy = RetryPolicy::default();
+        let error = |kind| Error::Io(io::Error::from(kind));
+        assert!(policy.is_retryable(&error(io::ErrorKind::ConnectionRefused)));
+        assert!(policy.is_retryable(&error(io::ErrorKind::TimedOut)));
+        assert!(!policy.is_retryable(&error(io::ErrorKind::InvalidData)));
+
+        let policy = RetryPolicy {
+            retry_connection_errors: false,
+            ..RetryPolicy::default()
+        };
+        assert!(!policy.is_retryable(&error(io::ErrorKind::ConnectionRefused)));
+    }
+
+    #[tokio::test]
+    async fn timeouts_are_retried_for_idempotent_methods_only() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::ZERO,
+            retry_non_idempotent: true,
+            ..RetryPolicy::default()
+        };
+        let attempts = |request: RpcRequest, kind: io::ErrorKind| {
+            let policy = policy.clone();
+            async move {
+                let mut attempts = 0;
+                let result: crate::Result<()> = policy
+                    .run(&[request], || {
+                        attempts += 1;
+                        async { Err(Error::Io(io::Error::from(kind))) }
+                    })
+                    .await;
+                assert!(result.is_err());
+                attempts
+            }
+        };
+        let add = || RpcRequest::torrent_add(TorrentAddArgs::default());
+        let get = || RpcRequest::torrent_get(None, Some(vec![Id::Id(1)]));
+        assert_eq!(attempts(get(), io::ErrorKind::TimedOut).await, 3);
+        assert_eq!(attempts(add(), io::ErrorKind::TimedOut).await, 1);
+        assert_eq!(attempts(add(), io::ErrorKind::ConnectionRefused).await, 3);
+    }
+
+    #[test]
+    fn non_idempotent_methods() {
+        let add = RpcRequest::torrent_add(TorrentAddArgs::default());
+        let get = RpcRequest::torrent_get(None, Some(vec![Id::Id(1)]));
+
+        let policy = RetryPolicy::default();
+        assert!(!policy.allows(&add));
+        assert!(policy.allows(&get));
+
+        let policy = RetryPolicy {
+            retry_non_idempotent: true,
+            ..RetryPolicy::default()
+        };
+        assert!(policy.allows(&add));
+        assert!(!RetryPolicy::none().allows(&get));
+    }
+}
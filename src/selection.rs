@@ -0,0 +1,471 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+//! Selection of the files of a torrent by name, see [`FileSelection`].
+//!
+//! The daemon identifies files by their index in the torrent. A [`FileSelection`] turns rules
+//! on file names into the index lists of [`TorrentAddArgs`] and [`TorrentSetArgs`], for a
+//! torrent about to be added ([`Metainfo`]) as well as for one the daemon already has
+//! ([`Torrent`]):
+//!
+//! ```no_run
+//! use transmission_rpc::{
+//!     metainfo::Metainfo,
+//!     selection::{FileSelection, Pattern},
+//!     types::{Id, Priority, TorrentAddArgs, TorrentGetField, TorrentSetArgs},
+//!     TransClient,
+//! };
+//!
+//! # #[tokio::main]
+//! # async fn main() -> transmission_rpc::Result<()> {
+//! let url = "http://localhost:9091/transmission/rpc".parse().expect("valid URL");
+//! let client = TransClient::new(url);
+//! let selection = FileSelection::new()
+//!     .skip(Pattern::glob("*.nfo").expect("valid glob"))
+//!     .priority(Pattern::glob("*.mkv").expect("valid glob"), Priority::High);
+//!
+//! let metainfo = Metainfo::from_file("show.torrent")?;
+//! let add = selection.select(&metainfo).apply_to_add(TorrentAddArgs::from(&metainfo));
+//! client.torrent_add(add).await?;
+//!
+//! let fields = vec![TorrentGetField::Id, TorrentGetField::Files];
+//! let torrents = client.torrent_get(Some(fields), vec![Id::Id(1)]).await?;
+//! let set = selection
+//!     .select(&torrents.arguments.torrents[0])
+//!     .apply_to_set(TorrentSetArgs::new());
+//! client.torrent_set(set, vec![Id::Id(1)]).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use crate::{
+    metainfo::Metainfo,
+    types::{Priority, Torrent, TorrentAddArgs, TorrentSetArgs},
+};
+
+/// Rules deciding which files of a torrent to download and with which priority.
+///
+/// Rules apply in order, a later rule overriding an earlier one for the files both match.
+/// Files matched by no rule are left out of the resulting [`FileIndices`], so they keep the
+/// daemon's defaults when added and their current state when set.
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct FileSelection {
+    rules: Vec<(Pattern, Rule)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    Wanted(bool),
+    Priority(Priority),
+}
+
+impl FileSelection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Downloads the files matching `pattern`.
+    pub fn want(mut self, pattern: Pattern) -> Self {
+        self.rules.push((pattern, Rule::Wanted(true)));
+        self
+    }
+
+    /// Does not download the files matching `pattern`.
+    pub fn skip(mut self, pattern: Pattern) -> Self {
+        self.rules.push((pattern, Rule::Wanted(false)));
+        self
+    }
+
+    /// Downloads the files matching `pattern` with `priority`.
+    pub fn priority(mut self, pattern: Pattern, priority: Priority) -> Self {
+        self.rules.push((pattern, Rule::Priority(priority)));
+        self
+    }
+
+    /// Applies the rules to the files of `torrent`.
+    pub fn select(&self, torrent: &(impl FileNames + ?Sized)) -> FileIndices {
+        let mut indices = FileIndices::default();
+        for (index, name) in torrent.file_names().into_iter().enumerate() {
+            let mut wanted = None;
+            let mut priority = None;
+            for (pattern, rule) in &self.rules {
+                if pattern.matches(name) {
+                    match *rule {
+                        Rule::Wanted(value) => wanted = Some(value),
+                        Rule::Priority(value) => priority = Some(value),
+                    }
+                }
+            }
+            match wanted {
+                Some(true) => indices.wanted.push(index),
+                Some(false) => indices.unwanted.push(index),
+                None => {}
+            }
+            match priority {
+                Some(Priority::High) => indices.priority_high.push(index),
+                Some(Priority::Normal) => indices.priority_normal.push(index),
+                Some(Priority::Low) => indices.priority_low.push(index),
+                None => {}
+            }
+        }
+        indices
+    }
+}
+
+/// File indices produced by [`FileSelection::select`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileIndices {
+    pub wanted: Vec<usize>,
+    pub unwanted: Vec<usize>,
+    pub priority_high: Vec<usize>,
+    pub priority_normal: Vec<usize>,
+    pub priority_low: Vec<usize>,
+}
+
+impl FileIndices {
+    /// Sets the file fields of `args` that have at least one index.
+    #[must_use]
+    pub fn apply_to_add(&self, mut args: TorrentAddArgs) -> TorrentAddArgs {
+        let set = |field: &mut Option<Vec<i32>>, indices: &[usize]| {
+            if !indices.is_empty() {
+                *field = Some(indices.iter().map(|&index| index as i32).collect());
+            }
+        };
+        set(&mut args.files_wanted, &self.wanted);
+        set(&mut args.files_unwanted, &self.unwanted);
+        set(&mut args.priority_high, &self.priority_high);
+        set(&mut args.priority_normal, &self.priority_normal);
+        set(&mut args.priority_low, &self.priority_low);
+        args
+    }
+
+    /// Sets the file fields of `args` that have at least one index.
+    #[must_use]
+    pub fn apply_to_set(&self, mut args: TorrentSetArgs) -> TorrentSetArgs {
+        let set = |field: &mut Option<Vec<usize>>, indices: &[usize]| {
+            if !indices.is_empty() {
+                *field = Some(indices.to_vec());
+            }
+        };
+        set(&mut args.files_wanted, &self.wanted);
+        set(&mut args.files_unwanted, &self.unwanted);
+        set(&mut args.priority_high, &self.priority_high);
+        set(&mut args.priority_normal, &self.priority_normal);
+        set(&mut args.priority_low, &self.priority_low);
+        args
+    }
+}
+
+/// Torrents whose files can be selected, in the order of their indices.
+pub trait FileNames {
+    fn file_names(&self) -> Vec<&str>;
+}
+
+impl FileNames for Metainfo {
+    fn file_names(&self) -> Vec<&str> {
+        self.files.iter().map(|file| file.name.as_str()).collect()
+    }
+}
+
+/// The torrent must have been fetched with
+/// [`TorrentGetField::Files`](crate::types::TorrentGetField::Files), it has no files otherwise.
+impl FileNames for Torrent {
+    fn file_names(&self) -> Vec<&str> {
+        self.files
+            .iter()
+            .flatten()
+            .map(|file| file.name.as_str())
+            .collect()
+    }
+}
+
+impl FileNames for [&str] {
+    fn file_names(&self) -> Vec<&str> {
+        self.to_vec()
+    }
+}
+
+/// A pattern matched against file names, which include the name of the torrent as their
+/// first `/`-separated component.
+#[derive(Debug, Clone)]
+pub struct Pattern(Matcher);
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    /// Matched against the whole name if `path` is set, against the last component otherwise.
+    Glob { tokens: Vec<Token>, path: bool },
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    /// `?`
+    Any,
+    /// `*`
+    Star,
+    /// `**`
+    Anything,
+    /// `**/`, any number of directories
+    Directories,
+    /// `[...]`
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Pattern {
+    /// A shell-like glob: `*` matches anything but `/`, `**` anything, `?` a single character
+    /// other than `/`, `[a-z]` and `[!a-z]` a character in or out of a set, and `\` escapes
+    /// the next character. A glob without `/`, like `*.nfo`, is matched against the last
+    /// component of the names, in any directory.
+    ///
+    /// # Errors
+    ///
+    /// If a `[` is not closed, or `glob` ends with a `\`
+    pub fn glob(glob: &str) -> Result<Self, PatternError> {
+        let mut tokens = Vec::new();
+        let mut chars = glob.chars().peekable();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '?' => Token::Any,
+                '*' if chars.peek() == Some(&'*') => {
+                    while chars.next_if_eq(&'*').is_some() {}
+                    match chars.next_if_eq(&'/') {
+                        Some(_) => Token::Directories,
+                        None => Token::Anything,
+                    }
+                }
+                '*' => Token::Star,
+                '[' => class(&mut chars).ok_or_else(|| PatternError::new(glob, "unclosed ["))?,
+                '\\' => Token::Char(
+                    chars
+                        .next()
+                        .ok_or_else(|| PatternError::new(glob, "trailing \\"))?,
+                ),
+                c => Token::Char(c),
+            };
+            tokens.push(token);
+        }
+        Ok(Pattern(Matcher::Glob {
+            path: glob.contains('/'),
+            tokens,
+        }))
+    }
+
+    /// A regular expression, found anywhere in the names unless anchored with `^` or `$`.
+    ///
+    /// # Errors
+    ///
+    /// If `regex` is not a valid regular expression
+    #[cfg(feature = "regex")]
+    pub fn regex(regex: &str) -> Result<Self, PatternError> {
+        regex::Regex::new(regex)
+            .map(Self::from)
+            .map_err(|e| PatternError::new(regex, &e.to_string()))
+    }
+
+    /// Returns whether the file `name` matches.
+    #[must_use]
+    pub fn matches(&self, name: &str) -> bool {
+        match &self.0 {
+            Matcher::Glob { tokens, path } => {
+                let name = if *path {
+                    name
+                } else {
+                    name.rsplit('/').next().unwrap_or(name)
+                };
+                glob_matches(tokens, &name.chars().collect::<Vec<_>>())
+            }
+            #[cfg(feature = "regex")]
+            Matcher::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+#[cfg(feature = "regex")]
+impl From<regex::Regex> for Pattern {
+    fn from(regex: regex::Regex) -> Self {
+        Pattern(Matcher::Regex(regex))
+    }
+}
+
+/// Error returned when a [`Pattern`] is invalid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError(String);
+
+impl PatternError {
+    fn new(pattern: &str, reason: &str) -> Self {
+        PatternError(format!("invalid pattern {pattern:?}: {reason}"))
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/// Parses a character class, the opening `[` having been consumed.
+fn class(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Option<Token> {
+    let negated = chars.next_if(|&c| c == '!' || c == '^').is_some();
+    let mut ranges = Vec::new();
+    // A `]` right after the opening bracket is a member of the class.
+    if chars.next_if_eq(&']').is_some() {
+        ranges.push((']', ']'));
+    }
+    loop {
+        let start = chars.next()?;
+        if start == ']' {
+            return Some(Token::Class { negated, ranges });
+        }
+        match chars.next_if_eq(&'-') {
+            Some(_) => match chars.next_if(|&c| c != ']') {
+                Some(end) => ranges.push((start, end)),
+                None => ranges.extend([(start, start), ('-', '-')]),
+            },
+            None => ranges.push((start, start)),
+        }
+    }
+}
+
+/// Matches in `O(tokens × name)`: for each token in turn, `reached` holds whether each position
+/// of `name` can be reached by matching the tokens so far against what precedes it.
+fn glob_matches(tokens: &[Token], name: &[char]) -> bool {
+    let mut reached = vec![false; name.len() + 1];
+    reached[0] = true;
+    for token in tokens {
+        let mut next = vec![false; name.len() + 1];
+        // Whether an earlier position was reached, for the tokens spanning several characters
+        let mut from = false;
+        for (i, slot) in next.iter_mut().enumerate() {
+            let previous = i.checked_sub(1).map(|i| name[i]);
+            *slot = match token {
+                Token::Star => {
+                    from = reached[i] || (from && previous != Some('/'));
+                    from
+                }
+                Token::Anything => {
+                    from |= reached[i];
+                    from
+                }
+                Token::Directories => {
+                    let after_directory = from && previous == Some('/');
+                    from |= reached[i];
+                    reached[i] || after_directory
+                }
+                Token::Any => previous.is_some_and(|c| c != '/') && reached[i - 1],
+                Token::Char(expected) => previous == Some(*expected) && reached[i - 1],
+                Token::Class { negated, ranges } => previous.is_some_and(|c| {
+                    let member = ranges
+                        .iter()
+                        .any(|&(start, end)| (start..=end).contains(&c));
+                    c != '/' && member != *negated && reached[i - 1]
+                }),
+            };
+        }
+        reached = next;
+    }
+    reached[name.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileIndices, FileSelection, Pattern, PatternError};
+    use crate::types::{Priority, TorrentAddArgs, TorrentSetArgs};
+
+    const FILES: &[&str] = &[
+        "show/s01/e01.mkv",
+        "show/s01/e01.nfo",
+        "show/s01/e01.srt",
+        "show/sample/e01.mkv",
+        "show/readme.txt",
+    ];
+
+    fn matching(glob: &str) -> Result<Vec<&'static str>, PatternError> {
+        let pattern = Pattern::glob(glob)?;
+        Ok(FILES
+            .iter()
+            .copied()
+            .filter(|name| pattern.matches(name))
+            .collect())
+    }
+
+    #[test]
+    fn globs() -> Result<(), PatternError> {
+        assert_eq!(matching("*.mkv")?, [FILES[0], FILES[3]]);
+        assert_eq!(matching("show/*.txt")?, [FILES[4]]);
+        assert_eq!(matching("show/*.mkv")?, Vec::<&str>::new());
+        assert_eq!(matching("**/sample/**")?, [FILES[3]]);
+        assert_eq!(matching("show/**.mkv")?, [FILES[0], FILES[3]]);
+        assert_eq!(matching("e0?.[ns][fr]?")?, [FILES[1], FILES[2]]);
+        assert_eq!(matching("*.[!m]*")?, [FILES[1], FILES[2], FILES[4]]);
+        assert_eq!(matching("[a-e]*")?, &FILES[..4]);
+        assert_eq!(matching("*[.-]txt")?, [FILES[4]]);
+        assert!(Pattern::glob("[]]").is_ok_and(|p| p.matches("a/]")));
+        assert!(Pattern::glob("*.[mkv").is_err());
+        assert!(Pattern::glob("*\\").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn globs_match_in_linear_steps() -> Result<(), PatternError> {
+        let name = format!("{}/{}", "a".repeat(200), "a".repeat(200));
+        assert!(!Pattern::glob("*a*a*a*a*a*a*a*a*b")?.matches(&name));
+        assert!(!Pattern::glob("**a**a**a**a**a**a**a**a**b")?.matches(&name));
+        assert!(Pattern::glob("**/*a*a*a*a*a*a*a*a")?.matches(&name));
+        Ok(())
+    }
+
+    #[test]
+    fn later_rules_win() -> Result<(), PatternError> {
+        let selection = FileSelection::new()
+            .skip(Pattern::glob("*")?)
+            .want(Pattern::glob("*.mkv")?)
+            .skip(Pattern::glob("**/sample/*")?)
+            .priority(Pattern::glob("*.mkv")?, Priority::High)
+            .priority(Pattern::glob("*.srt")?, Priority::Low);
+        let indices = selection.select(FILES);
+        assert_eq!(
+            indices,
+            FileIndices {
+                wanted: vec![0],
+                unwanted: vec![1, 2, 3, 4],
+                priority_high: vec![0, 3],
+                priority_normal: vec![],
+                priority_low: vec![2],
+            }
+        );
+        assert_eq!(
+            FileSelection::new()
+                .skip(Pattern::glob("show/sample/*")?)
+                .select(FILES)
+                .unwanted,
+            [3]
+        );
+
+        let add = indices.apply_to_add(TorrentAddArgs::default());
+        assert_eq!(add.files_unwanted, Some(vec![1, 2, 3, 4]));
+        assert_eq!(add.priority_normal, None);
+        let set = indices.apply_to_set(TorrentSetArgs::default());
+        assert_eq!(set.files_wanted, Some(vec![0]));
+        assert_eq!(set.priority_low, Some(vec![2]));
+        Ok(())
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regexes() -> Result<(), PatternError> {
+        let selection = FileSelection::new().skip(Pattern::regex(r"(?i)/sample/|\.nfo$")?);
+        assert_eq!(selection.select(FILES).unwanted, [1, 3]);
+        assert!(Pattern::regex("(").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+//! Storage of the session id handed out by the daemon, see [`SessionStorage`].
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use tokio::sync::{RwLock, RwLockWriteGuard};
+
+use crate::trace;
+
+/// Where an [`RpcClient`](crate::RpcClient) keeps its [`Session`].
+///
+/// The storage decides how clones of a client relate: [`OwnedSessionId`] belongs to a single
+/// client, while [`SharedSessionId`] is shared by all the clones of a client, so that a session
+/// id renewed by one of them is reused by the others.
+pub trait SessionStorage: Send + Sync {
+    fn from_session(session: Session) -> Self;
+
+    fn session(&self) -> &Session;
+}
+
+/// The `X-Transmission-Session-Id` of the daemon, renewed once for all concurrent requests.
+///
+/// When the daemon rejects a session id (after a restart, or on the first request), every
+/// request in flight gets a 409 carrying the new id. The first one to come back stores it, the
+/// others retry with it instead of renewing again. While no id is known at all, a single request
+/// performs the handshake and the others wait for its outcome.
+#[derive(Debug, Default)]
+pub struct Session {
+    id: RwLock<Option<String>>,
+    renewals: AtomicU64,
+}
+
+/// Held by the request performing the handshake, see [`Session::current`].
+pub(crate) type Handshake<'a> = RwLockWriteGuard<'a, Option<String>>;
+
+impl Session {
+    pub(crate) fn new(id: Option<String>) -> Session {
+        Session {
+            id: RwLock::new(id),
+            renewals: AtomicU64::new(0),
+        }
+    }
+
+    /// Number of times the session id was renewed.
+    pub fn renewals(&self) -> u64 {
+        self.renewals.load(Ordering::Relaxed)
+    }
+
+    /// Returns the session id to send. If none is known yet, the caller also gets the
+    /// [`Handshake`], which makes the other callers wait until it is passed to
+    /// [`renew`](Self::renew) or dropped.
+    pub(crate) async fn current(&self) -> (Option<String>, Option<Handshake<'_>>) {
+        if let Some(id) = self.id.read().await.clone() {
+            return (Some(id), None);
+        }
+        let handshake = self.id.write().await;
+        match handshake.clone() {
+            Some(id) => (Some(id), None),
+            None => (None, Some(handshake)),
+        }
+    }
+
+    /// Stores `fresh`, received in a 409 answering a request sent with `stale`, unless another
+    /// request already renewed it. Returns the session id to retry with.
+    pub(crate) async fn renew(
+        &self,
+        handshake: Option<Handshake<'_>>,
+        stale: Option<&str>,
+        fresh: &str,
+    ) -> String {
+        let mut id = match handshake {
+            Some(handshake) => handshake,
+            None => self.id.write().await,
+        };
+        if id.as_deref() == stale {
+            *id = Some(fresh.to_string());
+            let renewals = self.renewals.fetch_add(1, Ordering::Relaxed) + 1;
+            debug!("Session id renewed, {renewals} renewal(s) so far");
+            trace::session_renewed(renewals);
+        }
+        id.clone().unwrap_or_else(|| fresh.to_string())
+    }
+}
+
+/// Session of a single client, used by [`TransClient`](crate::TransClient).
+#[derive(Debug)]
+pub struct OwnedSessionId(Session);
+
+impl SessionStorage for OwnedSessionId {
+    fn from_session(session: Session) -> Self {
+        OwnedSessionId(session)
+    }
+
+    fn session(&self) -> &Session {
+        &self.0
+    }
+}
+
+/// Session shared between clones, used by `SharableTransClient`.
+#[derive(Debug, Clone)]
+pub struct SharedSessionId(Arc<Session>);
+
+impl SessionStorage for SharedSessionId {
+    fn from_session(session: Session) -> Self {
+        SharedSessionId(Arc::new(session))
+    }
+
+    fn session(&self) -> &Session {
+        &self.0
+    }
+}
@@ -0,0 +1,181 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+//! Instrumentation of the calls with `tracing`, when the `tracing` feature is enabled.
+//!
+//! Every call runs in a `transmission_rpc.call` span with the `method` and `tag` of the
+//! request, the number of `torrents` it targets, the current `attempt`, and once it completes,
+//! its `latency_ms` and the `response_bytes` received. Session id renewals are recorded as
+//! events of the span. Credentials are never recorded.
+//!
+//! A JSON-RPC batch runs in a single `transmission_rpc.batch` span instead, with the number of
+//! `calls` and their `tags` in place of the method, tag and torrents.
+//!
+//! Without the feature, these functions do nothing.
+
+use std::future::Future;
+
+use crate::types::RpcRequest;
+
+/// Runs `call`, the whole processing of `request` retries included, in its span.
+#[cfg(feature = "tracing")]
+pub(crate) async fn call<F: Future>(request: &RpcRequest, call: F) -> F::Output {
+    use std::time::Instant;
+
+    use tracing::{field, Instrument};
+
+    let torrents = request.ids().map(<[_]>::len);
+    let span = tracing::info_span!(
+        "transmission_rpc.call",
+        method = request.method(),
+        tag = request.tag(),
+        torrents,
+        attempt = field::Empty,
+        latency_ms = field::Empty,
+        response_bytes = field::Empty,
+    );
+    let start = Instant::now();
+    let output = call.instrument(span.clone()).await;
+    span.record("latency_ms", start.elapsed().as_millis() as u64);
+    output
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) async fn call<F: Future>(_request: &RpcRequest, call: F) -> F::Output {
+    call.await
+}
+
+/// Runs `call`, the exchange of the batch of `requests` retries included, in its span.
+#[cfg(feature = "tracing")]
+pub(crate) async fn batch<F: Future>(requests: &[RpcRequest], call: F) -> F::Output {
+    use std::time::Instant;
+
+    use tracing::{field, Instrument};
+
+    let tags: Vec<_> = requests.iter().filter_map(RpcRequest::tag).collect();
+    let span = tracing::info_span!(
+        "transmission_rpc.batch",
+        calls = requests.len(),
+        tags = ?tags,
+        attempt = field::Empty,
+        latency_ms = field::Empty,
+        response_bytes = field::Empty,
+    );
+    let start = Instant::now();
+    let output = call.instrument(span.clone()).await;
+    span.record("latency_ms", start.elapsed().as_millis() as u64);
+    output
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) async fn batch<F: Future>(_requests: &[RpcRequest], call: F) -> F::Output {
+    call.await
+}
+
+/// Records the attempt about to be made, starting at `1`.
+pub(crate) fn attempt(_attempt: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("attempt", _attempt);
+}
+
+/// Records the size of the response body.
+pub(crate) fn response(_bytes: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("response_bytes", _bytes);
+}
+
+/// Records a renewal of the session id.
+pub(crate) fn session_renewed(_renewals: u64) {
+    #[cfg(feature = "tracing")]
+    tracing::info!(renewals = _renewals, "session id renewed");
+}
+
+#[cfg(all(test, feature = "tracing", feature = "mock"))]
+mod tests {
+    use std::{
+        collections::HashMap,
+        fmt,
+        sync::{Arc, Mutex},
+    };
+
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+    use crate::{mock::MockServer, types::Result};
+
+    type Fields = HashMap<String, String>;
+
+    /// Keeps the fields of every span, and of the events along with the span they occur in.
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Captured>>);
+
+    #[derive(Default)]
+    struct Captured {
+        spans: HashMap<span::Id, (&'static str, Fields)>,
+        events: Vec<(Option<span::Id>, Fields)>,
+    }
+
+    struct Visitor<'a>(&'a mut Fields);
+
+    impl Visit for Visitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
+    impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Capture {
+        fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, _ctx: Context<'_, S>) {
+            let mut fields = Fields::new();
+            attrs.record(&mut Visitor(&mut fields));
+            let mut captured = self.0.lock().unwrap();
+            captured
+                .spans
+                .insert(id.clone(), (attrs.metadata().name(), fields));
+        }
+
+        fn on_record(&self, id: &span::Id, values: &span::Record<'_>, _ctx: Context<'_, S>) {
+            if let Some((_, fields)) = self.0.lock().unwrap().spans.get_mut(id) {
+                values.record(&mut Visitor(fields));
+            }
+        }
+
+        fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+            let mut fields = Fields::new();
+            event.record(&mut Visitor(&mut fields));
+            let span = ctx.current_span().id().cloned();
+            self.0.lock().unwrap().events.push((span, fields));
+        }
+    }
+
+    #[tokio::test]
+    async fn calls_are_traced() -> Result<()> {
+        let capture = Capture::default();
+        let _guard = tracing_subscriber::registry()
+            .with(capture.clone())
+            .set_default();
+
+        let server = MockServer::start().await?;
+        let client = server.client();
+        client.session_get(None).await?;
+
+        let captured = capture.0.lock().unwrap();
+        let (id, (_, fields)) = captured
+            .spans
+            .iter()
+            .find(|(_, (name, _))| *name == "transmission_rpc.call")
+            .expect("a call span");
+        assert_eq!(fields["method"], "\"session-get\"");
+        assert!(fields.contains_key("tag"));
+        assert_eq!(fields["attempt"], "1");
+        assert!(fields.contains_key("latency_ms"));
+        assert_ne!(fields["response_bytes"], "0");
+        assert!(captured.events.iter().any(|(span, fields)| {
+            span.as_ref() == Some(id) && fields["message"] == "session id renewed"
+        }));
+        Ok(())
+    }
+}
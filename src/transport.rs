@@ -0,0 +1,184 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+//! The layer moving RPC calls to the daemon, see [`Transport`].
+
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use base64::{engine::general_purpose::STANDARD as base64, Engine as _};
+use reqwest::{
+    header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE},
+    Client, StatusCode, Url,
+};
+
+use crate::{types::BasicAuth, Result};
+
+#[cfg(unix)]
+pub use self::unix::UnixTransport;
+
+#[cfg(unix)]
+mod unix;
+
+/// Header carrying the CSRF token of the daemon.
+pub const SESSION_ID: &str = "X-Transmission-Session-Id";
+
+/// An RPC call as sent over HTTP: a POST of a JSON `body` to `url`.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub url: Url,
+    /// `Content-Type`, and `Authorization` and [`SESSION_ID`] when known.
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Builds the POST of `body` to `url`, with the credentials and session id if any.
+    pub(crate) fn new(
+        url: Url,
+        auth: Option<&BasicAuth>,
+        session_id: Option<&str>,
+        body: Vec<u8>,
+    ) -> Self {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        if let Some(auth) = auth {
+            let credentials = base64.encode(format!("{}:{}", auth.user, auth.password));
+            let mut value = HeaderValue::try_from(format!("Basic {credentials}"))
+                .expect("base64 is a valid header value");
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+        if let Some(session_id) = session_id {
+            if let Ok(value) = HeaderValue::try_from(session_id) {
+                headers.insert(SESSION_ID, value);
+            }
+        }
+        HttpRequest { url, headers, body }
+    }
+
+    /// Returns the [`SESSION_ID`] header sent with this request.
+    #[must_use]
+    pub fn session_id(&self) -> Option<&str> {
+        self.headers.get(SESSION_ID).and_then(|id| id.to_str().ok())
+    }
+}
+
+/// The daemon's answer to an [`HttpRequest`].
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Returns the [`SESSION_ID`] header, sent by the daemon along with a 409.
+    #[must_use]
+    pub fn session_id(&self) -> Option<&str> {
+        self.headers.get(SESSION_ID).and_then(|id| id.to_str().ok())
+    }
+
+    /// Returns the body as text, replacing invalid UTF-8.
+    #[must_use]
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Future returned by [`Transport::send`].
+pub type TransportFuture<'a> = Pin<Box<dyn Future<Output = Result<HttpResponse>> + Send + 'a>>;
+
+/// Moves [`HttpRequest`]s to the daemon and brings back its [`HttpResponse`]s.
+///
+/// The client handles everything above HTTP (session id handshake, retries, decoding), so an
+/// implementation only has to deliver the request and return the response as-is, whatever its
+/// status. [`ReqwestTransport`] is used unless another one is configured with
+/// [`TransClientBuilder::transport`](crate::TransClientBuilder::transport).
+///
+/// Failures should be reported as [`Error::Io`](crate::Error::Io), wrapping foreign errors with
+/// [`std::io::Error::other`]. Connection and timeout [`ErrorKind`](std::io::ErrorKind)s are
+/// retried according to the client's [`RetryPolicy`](crate::RetryPolicy).
+///
+/// # Example
+///
+/// A test double answering every call with the same body:
+///
+/// ```
+/// use reqwest::{header::HeaderMap, StatusCode};
+/// use transmission_rpc::{
+///     transport::{HttpRequest, HttpResponse, Transport, TransportFuture},
+///     TransClientBuilder,
+/// };
+///
+/// struct Canned(&'static str);
+///
+/// impl Transport for Canned {
+///     fn send(&self, _request: HttpRequest) -> TransportFuture<'_> {
+///         Box::pin(async move {
+///             Ok(HttpResponse {
+///                 status: StatusCode::OK,
+///                 headers: HeaderMap::new(),
+///                 body: self.0.as_bytes().to_vec(),
+///             })
+///         })
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> transmission_rpc::types::Result<()> {
+/// let client = TransClientBuilder::new("http://localhost:9091/transmission/rpc".parse()?)
+///     .transport(Canned(r#"{"arguments":{"port-is-open":true},"result":"success"}"#))
+///     .build()?;
+/// assert!(client.port_test().await?.arguments.port_is_open);
+/// # Ok(())
+/// # }
+/// ```
+pub trait Transport: Send + Sync {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_>;
+}
+
+/// The default [`Transport`], over a [`reqwest::Client`].
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    #[must_use]
+    pub fn new(client: Client) -> Self {
+        ReqwestTransport { client }
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let rsp = self
+                .client
+                .post(request.url)
+                .headers(request.headers)
+                .body(request.body)
+                .send()
+                .await?;
+            Ok(HttpResponse {
+                status: rsp.status(),
+                headers: rsp.headers().clone(),
+                body: rsp.bytes().await?.to_vec(),
+            })
+        })
+    }
+}
+
+/// Returns the transport used by default for `url`: a [`UnixTransport`] for `unix:` addresses, a
+/// [`ReqwestTransport`] otherwise.
+pub(crate) fn for_url(url: &Url) -> Arc<dyn Transport> {
+    unix(url).unwrap_or_else(|| Arc::new(ReqwestTransport::default()))
+}
+
+/// Returns a [`UnixTransport`] if `url` is a `unix:` address.
+pub(crate) fn unix(url: &Url) -> Option<Arc<dyn Transport>> {
+    #[cfg(unix)]
+    return UnixTransport::from_url(url).map(|transport| Arc::new(transport) as _);
+    #[cfg(not(unix))]
+    None
+}
@@ -0,0 +1,271 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+use std::{
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH, TRANSFER_ENCODING},
+    StatusCode, Url,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::UnixStream,
+};
+
+use super::{HttpRequest, HttpResponse, Transport, TransportFuture};
+
+/// A [`Transport`] speaking HTTP over a Unix domain socket.
+///
+/// Transmission 4 listens on a socket when its `rpc-bind-address` is `unix:/path/to/socket`.
+/// Clients created for a `unix:` URL use this transport, the path of the URL being the socket:
+///
+/// ```no_run
+/// use transmission_rpc::TransClient;
+///
+/// # #[tokio::main]
+/// # async fn main() -> transmission_rpc::types::Result<()> {
+/// let client = TransClient::new("unix:/run/transmission/rpc.sock".parse()?);
+/// client.session_get(None).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Requests are sent to [`DEFAULT_RPC_PATH`](Self::DEFAULT_RPC_PATH); install the transport
+/// with [`TransClientBuilder::transport`](crate::TransClientBuilder::transport) to use
+/// another `rpc-url`. A connection is opened per request.
+#[derive(Debug, Clone)]
+pub struct UnixTransport {
+    socket: PathBuf,
+    rpc_path: String,
+}
+
+impl UnixTransport {
+    /// The default `rpc-url` of the daemon.
+    pub const DEFAULT_RPC_PATH: &'static str = "/transmission/rpc";
+
+    #[must_use]
+    pub fn new(socket: impl Into<PathBuf>) -> Self {
+        UnixTransport {
+            socket: socket.into(),
+            rpc_path: Self::DEFAULT_RPC_PATH.to_string(),
+        }
+    }
+
+    /// Sets the HTTP path the requests are sent to.
+    #[must_use]
+    pub fn rpc_path(mut self, rpc_path: impl Into<String>) -> Self {
+        self.rpc_path = rpc_path.into();
+        self
+    }
+
+    /// Returns a transport for `url` if it is a `unix:` address, its percent-decoded path being
+    /// the socket.
+    pub(crate) fn from_url(url: &Url) -> Option<Self> {
+        if url.scheme() != "unix" {
+            return None;
+        }
+        url.to_file_path().ok().map(UnixTransport::new)
+    }
+
+    fn encode(&self, request: &HttpRequest) -> Vec<u8> {
+        let mut out = format!(
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n",
+            self.rpc_path,
+            request.body.len()
+        )
+        .into_bytes();
+        for (name, value) in &request.headers {
+            out.extend_from_slice(name.as_str().as_bytes());
+            out.extend_from_slice(b": ");
+            out.extend_from_slice(value.as_bytes());
+            out.extend_from_slice(b"\r\n");
+        }
+        out.extend_from_slice(b"\r\n");
+        out.extend_from_slice(&request.body);
+        out
+    }
+}
+
+impl Transport for UnixTransport {
+    fn send(&self, request: HttpRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let mut stream = UnixStream::connect(&self.socket).await?;
+            stream.write_all(&self.encode(&request)).await?;
+            let mut raw = Vec::new();
+            stream.read_to_end(&mut raw).await?;
+            Ok(decode(&raw)?)
+        })
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Parses a complete HTTP/1.1 response, the connection having been closed by the daemon.
+fn decode(raw: &[u8]) -> io::Result<HttpResponse> {
+    let end = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "incomplete HTTP response"))?;
+    let head = std::str::from_utf8(&raw[..end]).map_err(|_| invalid("non UTF-8 HTTP head"))?;
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .and_then(|code| StatusCode::from_u16(code).ok())
+        .ok_or_else(|| invalid("malformed HTTP status line"))?;
+    let mut headers = HeaderMap::new();
+    for line in lines {
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| invalid("malformed HTTP header"))?;
+        let name = HeaderName::try_from(name.trim()).map_err(|_| invalid("bad header name"))?;
+        let value = HeaderValue::try_from(value.trim()).map_err(|_| invalid("bad header value"))?;
+        headers.append(name, value);
+    }
+
+    let rest = &raw[end + 4..];
+    let chunked = headers
+        .get(TRANSFER_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("chunked"));
+    let length = headers
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    let body = match (chunked, length) {
+        (true, _) => dechunk(rest)?,
+        (false, Some(length)) => rest
+            .get(..length)
+            .ok_or_else(|| io::Error::new(ErrorKind::UnexpectedEof, "truncated HTTP body"))?
+            .to_vec(),
+        (false, None) => rest.to_vec(),
+    };
+    Ok(HttpResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+fn dechunk(mut raw: &[u8]) -> io::Result<Vec<u8>> {
+    let truncated = || io::Error::new(ErrorKind::UnexpectedEof, "truncated chunked body");
+    let mut body = Vec::new();
+    loop {
+        let line_end = raw
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(truncated)?;
+        let size = std::str::from_utf8(&raw[..line_end])
+            .ok()
+            .and_then(|line| line.split(';').next())
+            .and_then(|size| usize::from_str_radix(size.trim(), 16).ok())
+            .ok_or_else(|| invalid("malformed chunk size"))?;
+        raw = &raw[line_end + 2..];
+        if size == 0 {
+            return Ok(body);
+        }
+        body.extend_from_slice(raw.get(..size).ok_or_else(truncated)?);
+        raw = raw.get(size + 2..).ok_or_else(truncated)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, process};
+
+    use reqwest::StatusCode;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::UnixListener,
+    };
+
+    use super::{decode, UnixTransport};
+    use crate::{types::Result, TransClient};
+
+    #[test]
+    fn socket_path_is_decoded() -> Result<()> {
+        let transport = UnixTransport::from_url(&"unix:/run/my%20dir/rpc.sock".parse()?);
+        assert_eq!(
+            transport.map(|transport| transport.socket),
+            Some(PathBuf::from("/run/my dir/rpc.sock"))
+        );
+        assert!(UnixTransport::from_url(&"http://localhost:9091/".parse()?).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn decode_chunked() -> Result<()> {
+        let rsp = decode(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nabcd\r\n2;x=y\r\nef\r\n0\r\n\r\n")?;
+        assert_eq!(rsp.status, StatusCode::OK);
+        assert_eq!(rsp.body, b"abcdef");
+        Ok(())
+    }
+
+    #[test]
+    fn decode_content_length() -> Result<()> {
+        let rsp = decode(b"HTTP/1.1 409 Conflict\r\nX-Transmission-Session-Id: abc\r\nContent-Length: 2\r\n\r\nok")?;
+        assert_eq!(rsp.status, StatusCode::CONFLICT);
+        assert_eq!(rsp.session_id(), Some("abc"));
+        assert_eq!(rsp.body, b"ok");
+        assert!(decode(b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nok").is_err());
+        Ok(())
+    }
+
+    /// Answers with a 409 until the session id is sent, then with a port test result.
+    async fn serve(listener: UnixListener) {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                return;
+            };
+            let mut raw = Vec::new();
+            let mut buf = [0; 1024];
+            // The whole request is read, or closing the socket would reset the client.
+            let request = loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                raw.extend_from_slice(&buf[..n]);
+                let request = String::from_utf8_lossy(&raw).to_lowercase();
+                let complete = request.split_once("\r\n\r\n").is_some_and(|(head, body)| {
+                    head.lines()
+                        .find_map(|line| line.strip_prefix("content-length: "))
+                        .is_some_and(|length| length.trim().parse() == Ok(body.len()))
+                });
+                if complete || n == 0 {
+                    break request;
+                }
+            };
+            assert!(request.starts_with("post /transmission/rpc http/1.1"));
+            let rsp = if request.contains("x-transmission-session-id: abc") {
+                let body = r#"{"arguments":{"port-is-open":true},"result":"success"}"#;
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{body}",
+                    body.len()
+                )
+            } else {
+                "HTTP/1.1 409 Conflict\r\nX-Transmission-Session-Id: abc\r\nContent-Length: 0\r\n\r\n"
+                    .to_string()
+            };
+            stream.write_all(rsp.as_bytes()).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn session_handshake_over_socket() -> Result<()> {
+        let socket = std::env::temp_dir().join(format!("transmission-rpc-{}.sock", process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let server = tokio::spawn(serve(UnixListener::bind(&socket)?));
+
+        let url = format!("unix:{}", socket.display()).parse()?;
+        let client = TransClient::new(url);
+        assert!(client.port_test().await?.arguments.port_is_open);
+
+        server.abort();
+        std::fs::remove_file(&socket)?;
+        Ok(())
+    }
+}
@@ -4,35 +4,67 @@
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
-pub(crate) use self::request::RpcRequest;
 pub use self::request::{
-    ArgumentFields, SessionSetArgs, TorrentAction, TorrentAddArgs, TorrentGetField,
-    TorrentRenamePathArgs, TorrentSetArgs, TrackerList,
+    ArgumentFields, GroupSetArgs, RpcRequest, SessionGetField, SessionSetArgs, TorrentAction,
+    TorrentAddArgs, TorrentGetField, TorrentRenamePathArgs, TorrentSetArgs, TrackerList,
 };
 
+pub use self::projection::TorrentProjection;
+
 pub use self::response::{
-    BlocklistUpdate, ErrorType, FreeSpace, Nothing, PortTest, RpcResponse, RpcResponseArgument,
-    SessionGet, SessionStats, Torrent, TorrentAddedOrDuplicate, TorrentRenamePath, TorrentStatus,
-    Torrents, TrackerState,
+    BandwidthGroup, BandwidthGroups, BlocklistUpdate, ErrorType, FreeSpace, Nothing, PortTest,
+    RpcResponse, RpcResponseArgument, SessionGet, SessionStats, Torrent, TorrentAddedOrDuplicate,
+    TorrentRenamePath, TorrentStatus, Torrents, TrackerState,
 };
 
 /// [`Torrent`] field sub-type. You probably won't need to interact with this directly.
 pub use self::response::{File, FileStat, Peer, PeersFrom, TrackerStat, Trackers};
 
+/// [`SessionGet`] field sub-type. You probably won't need to interact with this directly.
+pub use self::response::Units;
+
+pub(crate) use self::response::TorrentTable;
+
+mod projection;
 mod request;
 mod response;
 
 #[cfg(test)]
 mod tests;
 
+/// Catch-all result used by the examples and tests, where errors of the client mix with others,
+/// e.g. from parsing a URL or reading an environment variable.
+///
+/// Client calls return [`crate::Result`], whose [`crate::Error`] can be matched on; prefer it in
+/// code that only deals with the client.
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
-#[derive(Debug, Clone)]
+/// RPC protocol used to talk to the daemon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Protocol {
+    /// The original Transmission protocol (`method`/`arguments`/`result`, `tag`).
+    #[default]
+    Legacy,
+    /// JSON-RPC 2.0 with `snake_case` method names and keys, spoken by Transmission 4.1+.
+    JsonRpc,
+}
+
+#[derive(Clone)]
 pub struct BasicAuth {
     pub user: String,
     pub password: String,
 }
 
+/// Redacts the password, so that logging a client never leaks it.
+impl std::fmt::Debug for BasicAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BasicAuth")
+            .field("user", &self.user)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[serde(untagged)]
 pub enum Id {
@@ -40,6 +72,53 @@ pub enum Id {
     Hash(String),
 }
 
+/// Selects the torrents a request applies to.
+///
+/// Methods taking an `impl Into<Ids>` also accept a `Vec<Id>`, or an `Option<Vec<Id>>` where
+/// `None` means every torrent.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Ids {
+    /// Every torrent, the `ids` argument is omitted.
+    #[default]
+    All,
+    /// The listed torrents.
+    List(Vec<Id>),
+    /// The torrents that changed recently. In a torrent-get, the ids of recently removed
+    /// torrents are returned in [`Torrents::removed`].
+    RecentlyActive,
+}
+
+impl Ids {
+    pub(crate) fn is_all(&self) -> bool {
+        matches!(self, Ids::All)
+    }
+}
+
+impl Serialize for Ids {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Ids::All => serializer.serialize_none(),
+            Ids::List(ids) => ids.serialize(serializer),
+            Ids::RecentlyActive => serializer.serialize_str("recently-active"),
+        }
+    }
+}
+
+impl From<Vec<Id>> for Ids {
+    fn from(ids: Vec<Id>) -> Self {
+        Ids::List(ids)
+    }
+}
+
+impl From<Option<Vec<Id>>> for Ids {
+    fn from(ids: Option<Vec<Id>>) -> Self {
+        ids.map_or(Ids::All, Ids::List)
+    }
+}
+
 #[derive(Serialize_repr, Deserialize_repr, Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(i8)]
 pub enum Priority {
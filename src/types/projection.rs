@@ -0,0 +1,152 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+use enum_iterator::all;
+use serde::{
+    de::{self, DeserializeOwned, Visitor},
+    forward_to_deserialize_any, Deserializer,
+};
+
+use super::{Torrent, TorrentGetField};
+
+/// A user-defined view of a torrent, see [`TransClient::torrent_get_as`].
+///
+/// By default the requested fields are derived from the serde field names of the struct, so
+/// fields must be named like the [`Torrent`] fields they map to (usually with
+/// `#[serde(rename_all = "camelCase")]`). Fields that are not a [`TorrentGetField`] are not
+/// requested. Override [`fields`](Self::fields) when the struct is not a plain derived struct,
+/// e.g. when it uses `#[serde(flatten)]`.
+///
+/// # Example
+///
+/// ```
+/// use serde::Deserialize;
+/// use transmission_rpc::types::{TorrentGetField, TorrentProjection};
+///
+/// #[derive(Deserialize, Debug)]
+/// #[serde(rename_all = "camelCase")]
+/// struct Row {
+///     id: i64,
+///     name: String,
+///     percent_done: f32,
+/// }
+///
+/// impl TorrentProjection for Row {}
+///
+/// assert_eq!(
+///     Row::fields(),
+///     vec![
+///         TorrentGetField::Id,
+///         TorrentGetField::Name,
+///         TorrentGetField::PercentDone
+///     ]
+/// );
+/// ```
+///
+/// [`TransClient::torrent_get_as`]: crate::TransClient::torrent_get_as
+pub trait TorrentProjection: DeserializeOwned + std::fmt::Debug {
+    /// Fields to request from the daemon.
+    #[must_use]
+    fn fields() -> Vec<TorrentGetField> {
+        let mut names = FieldNames(&[]);
+        // `FieldNames` always fails, it only records the field names along the way.
+        let _ = Self::deserialize(&mut names);
+        names
+            .0
+            .iter()
+            .filter_map(|name| {
+                all::<TorrentGetField>().find(|field| {
+                    serde_json::to_value(field).is_ok_and(|value| value.as_str() == Some(name))
+                })
+            })
+            .collect()
+    }
+}
+
+impl TorrentProjection for Torrent {
+    fn fields() -> Vec<TorrentGetField> {
+        all::<TorrentGetField>().collect()
+    }
+}
+
+/// A [`Deserializer`] recording the field names of the struct asked for.
+struct FieldNames(&'static [&'static str]);
+
+impl<'de> Deserializer<'de> for &mut FieldNames {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0 = fields;
+        Err(de::Error::custom("field names recorded"))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::TorrentProjection;
+    use crate::types::{Result, RpcResponse, TorrentGetField, Torrents};
+
+    #[derive(Deserialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    struct Row {
+        id: i64,
+        hash_string: String,
+        #[serde(rename = "file-count")]
+        files: usize,
+        #[serde(skip)]
+        #[allow(dead_code)]
+        local: bool,
+        not_a_torrent_field: Option<String>,
+    }
+
+    impl TorrentProjection for Row {}
+
+    #[test]
+    fn fields_from_serde_names() {
+        assert_eq!(
+            Row::fields(),
+            vec![
+                TorrentGetField::Id,
+                TorrentGetField::HashString,
+                TorrentGetField::FileCount
+            ]
+        );
+    }
+
+    #[test]
+    fn deserialize_projection() -> Result<()> {
+        let v: RpcResponse<Torrents<Row>> = serde_json::from_str(
+            r#"
+            {
+                "arguments": {
+                    "torrents": [{ "id": 1, "hashString": "abc", "file-count": 3 }]
+                },
+                "result": "success"
+            }
+            "#,
+        )?;
+        let row = &v.arguments.torrents[0];
+        assert_eq!(row.id, 1);
+        assert_eq!(row.hash_string, "abc");
+        assert_eq!(row.files, 3);
+        assert_eq!(row.not_a_torrent_field, None);
+        Ok(())
+    }
+}
@@ -1,32 +1,107 @@
 // SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
 // SPDX-License-Identifier: MIT
 
-use super::{Id, IdleMode, Priority, RatioMode};
+use std::{fs, io, path::Path};
+
+use super::{Id, IdleMode, Ids, Priority, RatioMode};
+use crate::Error;
+use base64::{engine::general_purpose::STANDARD as base64, Engine as _};
 use enum_iterator::{all, Sequence};
 use serde::{Serialize, Serializer};
 use serde_with::skip_serializing_none;
 
+mod group_set;
 mod torrent_set;
 
+/// A single RPC call, built with one of the constructors below.
+///
+/// Client methods build these for you; they are only needed to call
+/// [`TransClient::call_with_retry`](crate::TransClient::call_with_retry) directly.
 #[skip_serializing_none]
 #[derive(Serialize, Debug)]
 pub struct RpcRequest {
     method: Method,
     arguments: Option<Args>,
+    tag: Option<u64>,
 }
 
 impl RpcRequest {
+    pub(crate) fn method(&self) -> &'static str {
+        self.method.as_str()
+    }
+
+    /// Sets the `tag` echoed back by the daemon in the response. Calls made through a client
+    /// get a generated tag unless one was set here.
+    #[must_use]
+    pub fn with_tag(mut self, tag: u64) -> RpcRequest {
+        self.tag = Some(tag);
+        self
+    }
+
+    #[must_use]
+    pub fn tag(&self) -> Option<u64> {
+        self.tag
+    }
+
+    /// Checks the arguments the daemon would reject without a useful error, before any call.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        match &self.arguments {
+            Some(Args::TorrentAdd(add)) if add.filename.is_none() && add.metainfo.is_none() => {
+                Err(Error::InvalidArguments {
+                    method: "torrent-add",
+                    reason: "either filename or metainfo must be provided",
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the torrents this request targets when they are listed.
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    pub(crate) fn ids(&self) -> Option<&[Id]> {
+        let ids = match self.arguments.as_ref()? {
+            Args::QueueMove(args) => &args.ids,
+            Args::TorrentGet(args) => &args.ids,
+            Args::TorrentAction(args) => &args.ids,
+            Args::TorrentSet(args) => &args.ids,
+            Args::TorrentRemove(args) => return Some(&args.ids),
+            Args::TorrentSetLocation(args) => return Some(&args.ids),
+            Args::TorrentRenamePath(args) => return Some(&args.ids),
+            _ => return None,
+        };
+        match ids {
+            Ids::List(ids) => Some(ids),
+            Ids::All | Ids::RecentlyActive => None,
+        }
+    }
+
+    /// Returns whether sending this request twice has the same effect as sending it once.
+    pub(crate) fn is_idempotent(&self) -> bool {
+        !matches!(
+            self.method,
+            Method::TorrentAdd
+                | Method::TorrentRemove
+                | Method::TorrentSetLocation
+                | Method::TorrentRenamePath
+                | Method::QueueMoveUp
+                | Method::QueueMoveDown
+                | Method::SessionClose
+        )
+    }
+
     pub fn session_set(args: SessionSetArgs) -> RpcRequest {
         RpcRequest {
             method: Method::SessionSet,
             arguments: Some(Args::SessionSet(args)),
+            tag: None,
         }
     }
 
-    pub fn session_get() -> RpcRequest {
+    pub fn session_get(fields: Option<Vec<SessionGetField>>) -> RpcRequest {
         RpcRequest {
             method: Method::SessionGet,
-            arguments: None,
+            arguments: fields.map(|fields| Args::SessionGet(SessionGetArgs { fields })),
+            tag: None,
         }
     }
 
@@ -34,6 +109,7 @@ impl RpcRequest {
         RpcRequest {
             method: Method::SessionStats,
             arguments: None,
+            tag: None,
         }
     }
 
@@ -41,6 +117,7 @@ impl RpcRequest {
         RpcRequest {
             method: Method::SessionClose,
             arguments: None,
+            tag: None,
         }
     }
 
@@ -48,6 +125,7 @@ impl RpcRequest {
         RpcRequest {
             method: Method::BlocklistUpdate,
             arguments: None,
+            tag: None,
         }
     }
 
@@ -55,6 +133,7 @@ impl RpcRequest {
         RpcRequest {
             method: Method::FreeSpace,
             arguments: Some(Args::FreeSpace(FreeSpaceArgs { path })),
+            tag: None,
         }
     }
 
@@ -62,54 +141,75 @@ impl RpcRequest {
         RpcRequest {
             method: Method::PortTest,
             arguments: None,
+            tag: None,
         }
     }
 
-    pub fn queue_move_top(ids: Vec<Id>) -> RpcRequest {
+    pub fn queue_move_top(ids: impl Into<Ids>) -> RpcRequest {
         RpcRequest {
             method: Method::QueueMoveTop,
-            arguments: Args::QueueMove(ids.into()).into(),
+            arguments: Args::QueueMove(QueueMoveArgs { ids: ids.into() }).into(),
+            tag: None,
         }
     }
 
-    pub fn queue_move_up(ids: Vec<Id>) -> RpcRequest {
+    pub fn queue_move_up(ids: impl Into<Ids>) -> RpcRequest {
         RpcRequest {
             method: Method::QueueMoveUp,
-            arguments: Args::QueueMove(ids.into()).into(),
+            arguments: Args::QueueMove(QueueMoveArgs { ids: ids.into() }).into(),
+            tag: None,
         }
     }
 
-    pub fn queue_move_down(ids: Vec<Id>) -> RpcRequest {
+    pub fn queue_move_down(ids: impl Into<Ids>) -> RpcRequest {
         RpcRequest {
             method: Method::QueueMoveDown,
-            arguments: Args::QueueMove(ids.into()).into(),
+            arguments: Args::QueueMove(QueueMoveArgs { ids: ids.into() }).into(),
+            tag: None,
         }
     }
 
-    pub fn queue_move_bottom(ids: Vec<Id>) -> RpcRequest {
+    pub fn queue_move_bottom(ids: impl Into<Ids>) -> RpcRequest {
         RpcRequest {
             method: Method::QueueMoveBottom,
-            arguments: Args::QueueMove(ids.into()).into(),
+            arguments: Args::QueueMove(QueueMoveArgs { ids: ids.into() }).into(),
+            tag: None,
         }
     }
 
-    pub fn torrent_get(fields: Option<Vec<TorrentGetField>>, ids: Option<Vec<Id>>) -> RpcRequest {
+    pub fn torrent_get(fields: Option<Vec<TorrentGetField>>, ids: impl Into<Ids>) -> RpcRequest {
         let fields = fields.unwrap_or_else(|| all::<TorrentGetField>().collect());
         let args = TorrentGetArgs {
             fields: fields.into(),
-            ids,
+            ids: ids.into(),
+            format: None,
         };
         RpcRequest {
             method: Method::TorrentGet,
             arguments: Some(Args::TorrentGet(args)),
+            tag: None,
         }
     }
 
-    pub fn torrent_set(mut args: TorrentSetArgs, ids: Option<Vec<Id>>) -> RpcRequest {
-        args.ids = ids;
+    /// Like [`torrent_get`](Self::torrent_get) but asks for the `"table"` format, where the
+    /// field names are only sent once instead of once per torrent.
+    pub fn torrent_get_table(
+        fields: Option<Vec<TorrentGetField>>,
+        ids: impl Into<Ids>,
+    ) -> RpcRequest {
+        let mut request = RpcRequest::torrent_get(fields, ids);
+        if let Some(Args::TorrentGet(args)) = &mut request.arguments {
+            args.format = Some(TorrentGetFormat::Table);
+        }
+        request
+    }
+
+    pub fn torrent_set(mut args: TorrentSetArgs, ids: impl Into<Ids>) -> RpcRequest {
+        args.ids = ids.into();
         RpcRequest {
             method: Method::TorrentSet,
             arguments: Some(Args::TorrentSet(args)),
+            tag: None,
         }
     }
 
@@ -120,6 +220,7 @@ impl RpcRequest {
                 ids,
                 delete_local_data,
             })),
+            tag: None,
         }
     }
 
@@ -127,13 +228,15 @@ impl RpcRequest {
         RpcRequest {
             method: Method::TorrentAdd,
             arguments: Some(Args::TorrentAdd(add)),
+            tag: None,
         }
     }
 
-    pub fn torrent_action(action: TorrentAction, ids: Vec<Id>) -> RpcRequest {
+    pub fn torrent_action(action: TorrentAction, ids: impl Into<Ids>) -> RpcRequest {
         RpcRequest {
             method: Method::TorrentAction(action),
-            arguments: Some(Args::TorrentAction(TorrentActionArgs { ids })),
+            arguments: Some(Args::TorrentAction(TorrentActionArgs { ids: ids.into() })),
+            tag: None,
         }
     }
 
@@ -149,6 +252,7 @@ impl RpcRequest {
                 location,
                 move_from,
             })),
+            tag: None,
         }
     }
 
@@ -160,6 +264,23 @@ impl RpcRequest {
                 path,
                 name,
             })),
+            tag: None,
+        }
+    }
+
+    pub fn group_get(group: Option<Vec<String>>) -> RpcRequest {
+        RpcRequest {
+            method: Method::GroupGet,
+            arguments: group.map(|group| Args::GroupGet(GroupGetArgs { group })),
+            tag: None,
+        }
+    }
+
+    pub fn group_set(args: GroupSetArgs) -> RpcRequest {
+        RpcRequest {
+            method: Method::GroupSet,
+            arguments: Some(Args::GroupSet(args)),
+            tag: None,
         }
     }
 }
@@ -184,6 +305,8 @@ enum Method {
     QueueMoveDown,
     QueueMoveTop,
     QueueMoveBottom,
+    GroupGet,
+    GroupSet,
 }
 
 impl Method {
@@ -209,6 +332,8 @@ impl Method {
             M::QueueMoveDown => "queue-move-down",
             M::QueueMoveTop => "queue-move-top",
             M::QueueMoveBottom => "queue-move-bottom",
+            M::GroupGet => "group-get",
+            M::GroupSet => "group-set",
         }
     }
 }
@@ -226,11 +351,13 @@ impl Serialize for Method {
 
 pub trait ArgumentFields {}
 impl ArgumentFields for TorrentGetField {}
+impl ArgumentFields for SessionGetField {}
 
 #[derive(Serialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum Args {
     FreeSpace(FreeSpaceArgs),
+    SessionGet(SessionGetArgs),
     SessionSet(SessionSetArgs),
     QueueMove(QueueMoveArgs),
     TorrentGet(TorrentGetArgs),
@@ -240,6 +367,8 @@ pub enum Args {
     TorrentSet(TorrentSetArgs),
     TorrentSetLocation(TorrentSetLocationArgs),
     TorrentRenamePath(TorrentRenamePathArgs),
+    GroupGet(GroupGetArgs),
+    GroupSet(GroupSetArgs),
 }
 
 #[derive(Serialize, Debug, Clone)]
@@ -247,6 +376,75 @@ pub struct FreeSpaceArgs {
     path: String,
 }
 
+#[derive(Serialize, Debug, Clone)]
+pub struct SessionGetArgs {
+    fields: Vec<SessionGetField>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Sequence, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SessionGetField {
+    AltSpeedDown,
+    AltSpeedEnabled,
+    AltSpeedTimeBegin,
+    AltSpeedTimeDay,
+    AltSpeedTimeEnabled,
+    AltSpeedTimeEnd,
+    AltSpeedUp,
+    BlocklistEnabled,
+    BlocklistSize,
+    BlocklistUrl,
+    CacheSizeMb,
+    ConfigDir,
+    DefaultTrackers,
+    DhtEnabled,
+    DownloadDir,
+    DownloadDirFreeSpace,
+    DownloadQueueEnabled,
+    DownloadQueueSize,
+    Encryption,
+    IdleSeedingLimitEnabled,
+    IdleSeedingLimit,
+    IncompleteDirEnabled,
+    IncompleteDir,
+    LpdEnabled,
+    PeerLimitGlobal,
+    PeerLimitPerTorrent,
+    PeerPortRandomOnStart,
+    PeerPort,
+    PexEnabled,
+    PortForwardingEnabled,
+    QueueStalledEnabled,
+    QueueStalledMinutes,
+    RenamePartialFiles,
+    RpcVersionMinimum,
+    RpcVersionSemver,
+    RpcVersion,
+    ScriptTorrentAddedEnabled,
+    ScriptTorrentAddedFilename,
+    ScriptTorrentDoneEnabled,
+    ScriptTorrentDoneFilename,
+    ScriptTorrentDoneSeedingEnabled,
+    ScriptTorrentDoneSeedingFilename,
+    SeedQueueEnabled,
+    SeedQueueSize,
+    #[serde(rename = "seedRatioLimit")]
+    SeedRatioLimit,
+    #[serde(rename = "seedRatioLimited")]
+    SeedRatioLimited,
+    SessionId,
+    SpeedLimitDownEnabled,
+    SpeedLimitDown,
+    SpeedLimitUpEnabled,
+    SpeedLimitUp,
+    StartAddedTorrents,
+    TcpEnabled,
+    TrashOriginalTorrentFiles,
+    Units,
+    UtpEnabled,
+    Version,
+}
+
 #[skip_serializing_none]
 #[derive(Serialize, Debug, Clone, Default)]
 #[serde(rename_all = "kebab-case")]
@@ -303,36 +501,47 @@ pub struct SessionSetArgs {
     pub utp_enabled: Option<bool>,
 }
 
-#[skip_serializing_none]
 #[derive(Serialize, Debug, Clone)]
 pub struct QueueMoveArgs {
-    ids: Vec<Id>,
+    #[serde(skip_serializing_if = "Ids::is_all")]
+    ids: Ids,
 }
 
 impl From<Vec<Id>> for QueueMoveArgs {
     fn from(ids: Vec<Id>) -> Self {
-        Self { ids }
+        Self { ids: ids.into() }
     }
 }
 
 #[derive(Serialize, Debug, Clone)]
 pub struct TorrentGetArgs {
     fields: Option<Vec<TorrentGetField>>,
-    ids: Option<Vec<Id>>,
+    #[serde(skip_serializing_if = "Ids::is_all")]
+    ids: Ids,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<TorrentGetFormat>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum TorrentGetFormat {
+    Table,
 }
 
 impl Default for TorrentGetArgs {
     fn default() -> Self {
         TorrentGetArgs {
             fields: all::<TorrentGetField>().collect::<Vec<_>>().into(),
-            ids: None,
+            ids: Ids::All,
+            format: None,
         }
     }
 }
 
 #[derive(Serialize, Debug, Clone)]
 pub struct TorrentActionArgs {
-    ids: Vec<Id>,
+    #[serde(skip_serializing_if = "Ids::is_all")]
+    ids: Ids,
 }
 #[derive(Serialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
@@ -368,7 +577,7 @@ pub struct TorrentAddArgs {
     /// filename or URL of the .torrent file
     pub filename: Option<String>,
     /// semi-optional
-    /// base64-encoded .torrent content
+    /// base64-encoded .torrent content, see [`TorrentAddArgs::from_bytes`]
     pub metainfo: Option<String>,
     pub paused: Option<bool>,
     pub peer_limit: Option<i64>,
@@ -389,6 +598,46 @@ pub struct TorrentAddArgs {
     pub labels: Option<Vec<String>>,
 }
 
+impl TorrentAddArgs {
+    /// Adds the `.torrent` file at `path`, read and encoded locally so that the daemon does not
+    /// need access to it.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be read
+    pub fn from_torrent_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::from_bytes(&fs::read(path)?))
+    }
+
+    /// Adds the torrent whose `.torrent` content is `bytes`.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        TorrentAddArgs {
+            metainfo: Some(base64.encode(bytes)),
+            ..TorrentAddArgs::default()
+        }
+    }
+
+    /// Adds the torrent of the `magnet:` URI `uri`.
+    #[must_use]
+    pub fn from_magnet(uri: impl Into<String>) -> Self {
+        Self::from_filename(uri)
+    }
+
+    /// Adds the `.torrent` file at `url`, downloaded by the daemon.
+    #[must_use]
+    pub fn from_url(url: impl Into<String>) -> Self {
+        Self::from_filename(url)
+    }
+
+    fn from_filename(filename: impl Into<String>) -> Self {
+        TorrentAddArgs {
+            filename: Some(filename.into()),
+            ..TorrentAddArgs::default()
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Sequence, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum TorrentGetField {
@@ -608,7 +857,8 @@ pub struct TorrentSetArgs {
     pub group: Option<String>,
     pub honors_session_limits: Option<bool>,
     // Don't expose the `ids` field as it is blindly overwritten by `torrent_set`.
-    ids: Option<Vec<Id>>,
+    #[serde(skip_serializing_if = "Ids::is_all")]
+    ids: Ids,
     pub labels: Option<Vec<String>>,
     pub location: Option<String>,
     #[serde(rename = "peer-limit")]
@@ -632,3 +882,166 @@ pub struct TorrentSetArgs {
     pub upload_limit: Option<usize>,
     pub upload_limited: Option<bool>,
 }
+
+#[derive(Serialize, Debug, Clone)]
+pub struct GroupGetArgs {
+    group: Vec<String>,
+}
+
+/// Defines request arguments for the [`group_set`] method.
+///
+/// Bandwidth groups are created on the fly by the daemon the first time they are set, so
+/// [`GroupSetArgs::name`] is the only required field. Torrents are assigned to a group with
+/// [`TorrentSetArgs::group`].
+///
+/// > Added in Transmission 4.0.0 (`rpc-version-semver` 5.3.0, `rpc-version`: 17).
+///
+/// # Examples
+///
+/// ```
+/// use transmission_rpc::types::GroupSetArgs;
+///
+/// let args = GroupSetArgs::new(String::from("tenant-a"))
+///                .speed_limit_down(1024)
+///                .speed_limit_down_enabled(true)
+///                .honors_session_limits(false);
+/// ```
+///
+/// [`group_set`]: crate::TransClient::group_set
+#[skip_serializing_none]
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct GroupSetArgs {
+    pub name: String,
+    #[serde(rename = "honorsSessionLimits")]
+    pub honors_session_limits: Option<bool>,
+    pub speed_limit_down_enabled: Option<bool>,
+    /// Maximum download speed (`KBps`).
+    pub speed_limit_down: Option<usize>,
+    pub speed_limit_up_enabled: Option<bool>,
+    /// Maximum upload speed (`KBps`).
+    pub speed_limit_up: Option<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        RpcRequest, SessionGetField, TorrentAction, TorrentAddArgs, TorrentGetField, TorrentSetArgs,
+    };
+    use crate::types::{Id, Ids};
+
+    #[test]
+    fn torrent_set_args_from_add_saturates_the_peer_limit() {
+        let peer_limit = |limit| {
+            TorrentSetArgs::from(&TorrentAddArgs {
+                peer_limit: Some(limit),
+                ..TorrentAddArgs::default()
+            })
+            .peer_limit
+        };
+        assert_eq!(peer_limit(50), Some(50));
+        assert_eq!(peer_limit(100_000), Some(u16::MAX));
+        assert_eq!(peer_limit(-1), Some(0));
+    }
+
+    #[test]
+    fn session_get_all_fields() {
+        assert_eq!(
+            serde_json::to_value(RpcRequest::session_get(None)).unwrap(),
+            json!({ "method": "session-get" })
+        );
+    }
+
+    #[test]
+    fn torrent_add_from_bytes() {
+        assert_eq!(
+            serde_json::to_value(RpcRequest::torrent_add(TorrentAddArgs::from_bytes(
+                b"d4:infod"
+            )))
+            .unwrap(),
+            json!({ "method": "torrent-add", "arguments": { "metainfo": "ZDQ6aW5mb2Q=" } })
+        );
+    }
+
+    #[test]
+    fn tagged_request() {
+        assert_eq!(
+            serde_json::to_value(RpcRequest::session_stats().with_tag(5)).unwrap(),
+            json!({ "method": "session-stats", "tag": 5 })
+        );
+    }
+
+    #[test]
+    fn session_get_selected_fields() {
+        let request = RpcRequest::session_get(Some(vec![
+            SessionGetField::Version,
+            SessionGetField::SeedRatioLimit,
+            SessionGetField::RpcVersionSemver,
+        ]));
+        assert_eq!(
+            serde_json::to_value(request).unwrap(),
+            json!({
+                "method": "session-get",
+                "arguments": {
+                    "fields": ["version", "seedRatioLimit", "rpc-version-semver"]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn torrent_get_ids() {
+        let request = |ids: Ids| {
+            serde_json::to_value(RpcRequest::torrent_get(
+                Some(vec![TorrentGetField::Id]),
+                ids,
+            ))
+            .unwrap()
+        };
+        assert_eq!(
+            request(Ids::All),
+            json!({ "method": "torrent-get", "arguments": { "fields": ["id"] } })
+        );
+        assert_eq!(
+            request(Ids::List(vec![Id::Id(1), Id::Hash("abc".to_string())])),
+            json!({ "method": "torrent-get", "arguments": { "fields": ["id"], "ids": [1, "abc"] } })
+        );
+        assert_eq!(
+            request(Ids::RecentlyActive),
+            json!({
+                "method": "torrent-get",
+                "arguments": { "fields": ["id"], "ids": "recently-active" }
+            })
+        );
+    }
+
+    #[test]
+    fn torrent_action_accepts_id_lists() {
+        assert_eq!(
+            serde_json::to_value(RpcRequest::torrent_action(
+                TorrentAction::Start,
+                vec![Id::Id(1)]
+            ))
+            .unwrap(),
+            json!({ "method": "torrent-start", "arguments": { "ids": [1] } })
+        );
+        assert_eq!(
+            serde_json::to_value(RpcRequest::queue_move_top(None)).unwrap(),
+            json!({ "method": "queue-move-top", "arguments": {} })
+        );
+    }
+
+    #[test]
+    fn torrent_get_table() {
+        assert_eq!(
+            serde_json::to_value(RpcRequest::torrent_get_table(
+                Some(vec![TorrentGetField::Id]),
+                None
+            ))
+            .unwrap(),
+            json!({ "method": "torrent-get", "arguments": { "fields": ["id"], "format": "table" } })
+        );
+    }
+}
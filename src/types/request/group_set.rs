@@ -0,0 +1,69 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+use super::GroupSetArgs;
+
+impl GroupSetArgs {
+    /// Creates a new [`GroupSetArgs`] for the group `name` with all other fields set to `None`.
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            ..Self::default()
+        }
+    }
+
+    pub fn honors_session_limits(mut self, honors_session_limits: bool) -> Self {
+        self.honors_session_limits = Some(honors_session_limits);
+        self
+    }
+    pub fn speed_limit_down_enabled(mut self, speed_limit_down_enabled: bool) -> Self {
+        self.speed_limit_down_enabled = Some(speed_limit_down_enabled);
+        self
+    }
+    pub fn speed_limit_down(mut self, speed_limit_down: usize) -> Self {
+        self.speed_limit_down = Some(speed_limit_down);
+        self
+    }
+    pub fn speed_limit_up_enabled(mut self, speed_limit_up_enabled: bool) -> Self {
+        self.speed_limit_up_enabled = Some(speed_limit_up_enabled);
+        self
+    }
+    pub fn speed_limit_up(mut self, speed_limit_up: usize) -> Self {
+        self.speed_limit_up = Some(speed_limit_up);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::GroupSetArgs;
+
+    #[test]
+    fn group_set_args_setter_speed_limit_down() {
+        let args = GroupSetArgs::new("test group".to_string());
+        assert_eq!(args.name, "test group");
+        assert_eq!(args.speed_limit_down, None);
+        let args = args.speed_limit_down(100);
+        assert_eq!(args.speed_limit_up, None);
+        assert_eq!(args.speed_limit_down, Some(100));
+    }
+
+    #[test]
+    fn group_set_args_serialize() {
+        let args = GroupSetArgs::new("test group".to_string())
+            .honors_session_limits(false)
+            .speed_limit_up_enabled(true)
+            .speed_limit_up(50);
+        assert_eq!(
+            serde_json::to_value(args).unwrap(),
+            json!({
+                "name": "test group",
+                "honorsSessionLimits": false,
+                "speed-limit-up-enabled": true,
+                "speed-limit-up": 50
+            })
+        );
+    }
+}
@@ -3,7 +3,24 @@
 
 use crate::types::{IdleMode, Priority, RatioMode};
 
-use super::{TorrentSetArgs, TrackerList};
+use super::{TorrentAddArgs, TorrentSetArgs, TrackerList};
+
+/// The settings of `add` that can be changed once the torrent is added: `labels`,
+/// `bandwidth_priority` and `peer_limit`, saturated to the range of `peer-limit`. The download
+/// dir is left out, set a `location` to move the torrent, see
+/// [`TransClient::ensure_torrent`](crate::TransClient::ensure_torrent).
+impl From<&TorrentAddArgs> for TorrentSetArgs {
+    fn from(add: &TorrentAddArgs) -> Self {
+        TorrentSetArgs {
+            labels: add.labels.clone(),
+            bandwidth_priority: add.bandwidth_priority,
+            peer_limit: add
+                .peer_limit
+                .map(|limit| limit.clamp(0, u16::MAX.into()) as u16),
+            ..TorrentSetArgs::default()
+        }
+    }
+}
 
 impl TorrentSetArgs {
     /// Creates a new [`TorrentSetArgs`] with all fields set to `None`.
@@ -1,22 +1,29 @@
 // SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
 // SPDX-License-Identifier: MIT
 
-use std::{collections::HashMap, net::IpAddr};
+use std::{marker::PhantomData, net::IpAddr};
 
 use base64::{engine::general_purpose::STANDARD as base64, Engine as _};
 use chrono::serde::ts_seconds::deserialize as from_ts;
 use chrono::{DateTime, Utc};
-use serde::de::{Deserializer, Error as _};
+use serde::de::{
+    value::MapAccessDeserializer, DeserializeSeed, Deserializer, Error as _, IntoDeserializer,
+    MapAccess, SeqAccess, Visitor,
+};
 use serde::Deserialize;
 use serde_json::Value;
 use serde_repr::*;
 
-use super::{Id, IdleMode, Priority, RatioMode};
+use super::{Id, IdleMode, Priority, RatioMode, TorrentProjection};
+use crate::magnet::MagnetLink;
 
 #[derive(Deserialize, Debug)]
 pub struct RpcResponse<T: RpcResponseArgument> {
     pub arguments: T,
     pub result: String,
+    /// The `tag` of the request, echoed back by the daemon.
+    #[serde(default)]
+    pub tag: Option<u64>,
 }
 
 impl<T: RpcResponseArgument> RpcResponse<T> {
@@ -26,19 +33,111 @@ impl<T: RpcResponseArgument> RpcResponse<T> {
 }
 pub trait RpcResponseArgument {}
 
+/// Response arguments of the [`session_get`] method.
+///
+/// Every field is optional as only the requested [`SessionGetField`]s are returned by the
+/// daemon, and older daemons may not know about newer keys.
+///
+/// [`session_get`]: crate::TransClient::session_get
+/// [`SessionGetField`]: super::SessionGetField
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct SessionGet {
-    pub blocklist_enabled: bool,
-    pub download_dir: String,
-    pub encryption: String,
-    pub peer_port: i32,
-    pub rpc_version: i32,
-    pub rpc_version_minimum: i32,
-    pub version: String,
+    /// Max global download speed (`KBps`) while alternative speed limits are enabled.
+    pub alt_speed_down: Option<i32>,
+    pub alt_speed_enabled: Option<bool>,
+    /// Minutes after midnight at which the alternative speed schedule begins.
+    pub alt_speed_time_begin: Option<i32>,
+    /// Bitmask of days of the week the alternative speed schedule applies to (Sunday = 1).
+    pub alt_speed_time_day: Option<i32>,
+    pub alt_speed_time_enabled: Option<bool>,
+    /// Minutes after midnight at which the alternative speed schedule ends.
+    pub alt_speed_time_end: Option<i32>,
+    /// Max global upload speed (`KBps`) while alternative speed limits are enabled.
+    pub alt_speed_up: Option<i32>,
+    pub blocklist_enabled: Option<bool>,
+    pub blocklist_size: Option<i32>,
+    pub blocklist_url: Option<String>,
+    pub cache_size_mb: Option<i32>,
+    /// Location of Transmission's configuration directory.
+    pub config_dir: Option<String>,
+    /// Announce urls, one per line, with an empty line between tiers.
+    ///
+    /// Added in Transmission 4.0.0 (`rpc-version-semver`: 5.3.0, `rpc-version`: 17).
+    pub default_trackers: Option<String>,
+    pub dht_enabled: Option<bool>,
+    pub download_dir: Option<String>,
+    /// ⚠ Deprecated in Transmission 4.0.0 (`rpc-version-semver`: 5.3.0, `rpc-version`: 17);
+    /// use [`free_space`](crate::TransClient::free_space) instead.
+    pub download_dir_free_space: Option<i64>,
+    pub download_queue_enabled: Option<bool>,
+    pub download_queue_size: Option<i32>,
+    pub encryption: Option<String>,
+    pub idle_seeding_limit_enabled: Option<bool>,
+    pub idle_seeding_limit: Option<i32>,
+    pub incomplete_dir_enabled: Option<bool>,
+    pub incomplete_dir: Option<String>,
+    pub lpd_enabled: Option<bool>,
+    pub peer_limit_global: Option<i32>,
+    pub peer_limit_per_torrent: Option<i32>,
+    pub peer_port_random_on_start: Option<bool>,
+    pub peer_port: Option<i32>,
+    pub pex_enabled: Option<bool>,
+    pub port_forwarding_enabled: Option<bool>,
+    pub queue_stalled_enabled: Option<bool>,
+    pub queue_stalled_minutes: Option<i32>,
+    pub rename_partial_files: Option<bool>,
+    pub rpc_version_minimum: Option<i32>,
+    /// Added in Transmission 4.0.0 (`rpc-version-semver`: 5.3.0, `rpc-version`: 17).
+    pub rpc_version_semver: Option<String>,
+    pub rpc_version: Option<i32>,
+    pub script_torrent_added_enabled: Option<bool>,
+    pub script_torrent_added_filename: Option<String>,
+    pub script_torrent_done_enabled: Option<bool>,
+    pub script_torrent_done_filename: Option<String>,
+    pub script_torrent_done_seeding_enabled: Option<bool>,
+    pub script_torrent_done_seeding_filename: Option<String>,
+    pub seed_queue_enabled: Option<bool>,
+    pub seed_queue_size: Option<i32>,
+    #[serde(rename = "seedRatioLimit")]
+    pub seed_ratio_limit: Option<f32>,
+    #[serde(rename = "seedRatioLimited")]
+    pub seed_ratio_limited: Option<bool>,
+    /// The current `X-Transmission-Session-Id` value.
+    pub session_id: Option<String>,
+    pub speed_limit_down_enabled: Option<bool>,
+    pub speed_limit_down: Option<i32>,
+    pub speed_limit_up_enabled: Option<bool>,
+    pub speed_limit_up: Option<i32>,
+    pub start_added_torrents: Option<bool>,
+    /// Added in Transmission 4.1.0 (`rpc-version-semver`: 5.4.0, `rpc-version`: 18).
+    pub tcp_enabled: Option<bool>,
+    pub trash_original_torrent_files: Option<bool>,
+    pub units: Option<Units>,
+    pub utp_enabled: Option<bool>,
+    /// Long version string `$version ($revision)`.
+    pub version: Option<String>,
 }
 impl RpcResponseArgument for SessionGet {}
 
+/// Units used by the daemon when formatting speeds, sizes and memory amounts.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Units {
+    /// 4 strings: KB/s, MB/s, GB/s, TB/s
+    pub speed_units: Vec<String>,
+    /// Number of bytes in a KB (1000 for kB; 1024 for KiB)
+    pub speed_bytes: u64,
+    /// 4 strings: KB, MB, GB, TB
+    pub size_units: Vec<String>,
+    /// Number of bytes in a KB (1000 for kB; 1024 for KiB)
+    pub size_bytes: u64,
+    /// 4 strings: KB, MB, GB, TB
+    pub memory_units: Vec<String>,
+    /// Number of bytes in a KB (1000 for kB; 1024 for KiB)
+    pub memory_bytes: u64,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SessionStats {
@@ -261,6 +360,12 @@ impl Torrent {
             .map(Id::Id)
             .or_else(|| self.hash_string.clone().map(Id::Hash))
     }
+
+    /// Parses [`magnet_link`](Self::magnet_link), if it was requested and is valid.
+    #[must_use]
+    pub fn magnet(&self) -> Option<MagnetLink> {
+        self.magnet_link.as_deref()?.parse().ok()
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -276,8 +381,122 @@ pub struct Stats {
 #[derive(Deserialize, Debug)]
 pub struct Torrents<T> {
     pub torrents: Vec<T>,
+    /// Ids of the torrents removed recently, only sent for
+    /// [`Ids::RecentlyActive`](super::Ids::RecentlyActive) requests.
+    pub removed: Option<Vec<i64>>,
+}
+impl<T: TorrentProjection> RpcResponseArgument for Torrents<T> {}
+
+/// Arguments of a `"table"` format torrent-get, converted to [`Torrents`] by the client.
+#[derive(Deserialize, Debug)]
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
+pub(crate) struct TorrentTable<T> {
+    #[serde(deserialize_with = "from_table")]
+    torrents: Vec<T>,
+    removed: Option<Vec<i64>>,
+}
+impl<T: TorrentProjection> RpcResponseArgument for TorrentTable<T> {}
+
+impl<T> From<TorrentTable<T>> for Torrents<T> {
+    fn from(table: TorrentTable<T>) -> Self {
+        Torrents {
+            torrents: table.torrents,
+            removed: table.removed,
+        }
+    }
+}
+
+/// Deserializes a header row of field names followed by one row of values per item, without
+/// building the intermediate objects.
+fn from_table<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    struct TableVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> Visitor<'de> for TableVisitor<T> {
+        type Value = Vec<T>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a header row followed by value rows")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<T>, A::Error> {
+            let Some(header) = seq.next_element::<Vec<String>>()? else {
+                return Ok(Vec::new());
+            };
+            let mut rows = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+            while let Some(row) = seq.next_element_seed(TableRow {
+                header: &header,
+                marker: PhantomData,
+            })? {
+                rows.push(row);
+            }
+            Ok(rows)
+        }
+    }
+
+    deserializer.deserialize_seq(TableVisitor(PhantomData))
+}
+
+/// Deserializes a row of a table as if it were an object keyed by `header`.
+struct TableRow<'h, T> {
+    header: &'h [String],
+    marker: PhantomData<T>,
+}
+
+impl<'de, T: Deserialize<'de>> DeserializeSeed<'de> for TableRow<'_, T> {
+    type Value = T;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for TableRow<'_, T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a row of values")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, values: A) -> Result<T, A::Error> {
+        T::deserialize(MapAccessDeserializer::new(RowAccess {
+            keys: self.header.iter(),
+            values,
+        }))
+    }
+}
+
+struct RowAccess<'h, A> {
+    keys: std::slice::Iter<'h, String>,
+    values: A,
+}
+
+impl<'de, A: SeqAccess<'de>> MapAccess<'de> for RowAccess<'_, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        self.keys
+            .next()
+            .map(|key| seed.deserialize(key.as_str().into_deserializer()))
+            .transpose()
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, A::Error> {
+        self.values
+            .next_element_seed(seed)?
+            .ok_or_else(|| A::Error::custom("row is shorter than the header"))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.keys.len())
+    }
 }
-impl RpcResponseArgument for Torrents<Torrent> {}
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Trackers {
@@ -419,7 +638,9 @@ impl RpcResponseArgument for Nothing {}
 pub enum TorrentAddedOrDuplicate {
     TorrentDuplicate(Torrent),
     TorrentAdded(Torrent),
-    Error,
+    /// Neither `torrent-added` nor `torrent-duplicate` was returned, usually because the add
+    /// failed (see [`RpcResponse::result`]). Holds the arguments as returned.
+    Error(Value),
 }
 
 impl RpcResponseArgument for TorrentAddedOrDuplicate {}
@@ -429,18 +650,48 @@ impl<'de> Deserialize<'de> for TorrentAddedOrDuplicate {
     where
         D: Deserializer<'de>,
     {
-        let mut res: HashMap<String, Torrent> = Deserialize::deserialize(deserializer)?;
-
-        let added = res.remove("torrent-added");
-        let duplicate = res.remove("torrent-duplicate");
-        match (added, duplicate) {
-            (Some(torrent), None) => Ok(TorrentAddedOrDuplicate::TorrentAdded(torrent)),
-            (None, Some(torrent)) => Ok(TorrentAddedOrDuplicate::TorrentDuplicate(torrent)),
-            _ => Ok(TorrentAddedOrDuplicate::Error),
+        let mut res: serde_json::Map<String, Value> = Deserialize::deserialize(deserializer)?;
+
+        let torrent = |value| Torrent::deserialize(value).map_err(D::Error::custom);
+        match (res.remove("torrent-added"), res.remove("torrent-duplicate")) {
+            (Some(added), None) => Ok(TorrentAddedOrDuplicate::TorrentAdded(torrent(added)?)),
+            (None, Some(duplicate)) => Ok(TorrentAddedOrDuplicate::TorrentDuplicate(torrent(
+                duplicate,
+            )?)),
+            (added, duplicate) => {
+                res.extend(added.map(|added| ("torrent-added".to_string(), added)));
+                res.extend(duplicate.map(|duplicate| ("torrent-duplicate".to_string(), duplicate)));
+                Ok(TorrentAddedOrDuplicate::Error(Value::Object(res)))
+            }
         }
     }
 }
 
+/// A bandwidth group as returned by [`group_get`].
+///
+/// > Added in Transmission 4.0.0 (`rpc-version-semver` 5.3.0, `rpc-version`: 17).
+///
+/// [`group_get`]: crate::TransClient::group_get
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct BandwidthGroup {
+    #[serde(rename = "honorsSessionLimits")]
+    pub honors_session_limits: bool,
+    pub name: String,
+    pub speed_limit_down_enabled: bool,
+    /// Maximum download speed (`KBps`).
+    pub speed_limit_down: u64,
+    pub speed_limit_up_enabled: bool,
+    /// Maximum upload speed (`KBps`).
+    pub speed_limit_up: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BandwidthGroups {
+    pub group: Vec<BandwidthGroup>,
+}
+impl RpcResponseArgument for BandwidthGroups {}
+
 #[derive(Deserialize, Debug)]
 pub struct TorrentRenamePath {
     pub path: Option<String>,
@@ -451,7 +702,10 @@ impl RpcResponseArgument for TorrentRenamePath {}
 
 #[cfg(test)]
 mod tests {
-    use crate::types::{Result, RpcResponse, TorrentAddedOrDuplicate};
+    use crate::types::{
+        BandwidthGroups, Result, RpcResponse, SessionGet, Torrent, TorrentAddedOrDuplicate,
+        TorrentTable, Torrents,
+    };
     use serde_json;
     use serde_json::Value;
 
@@ -461,6 +715,9 @@ mod tests {
             serde_json::from_str(torrent_added_failure()).expect("Failure expected");
         println!("{v:#?}");
         assert!(!v.is_ok());
+        assert!(
+            matches!(v.arguments, TorrentAddedOrDuplicate::Error(Value::Object(a)) if a.is_empty())
+        );
     }
 
     #[test]
@@ -485,6 +742,203 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_session_get_all_fields() -> Result<()> {
+        let v: RpcResponse<SessionGet> = serde_json::from_str(
+            r#"
+            {
+                "arguments": {
+                    "alt-speed-down": 50,
+                    "alt-speed-enabled": false,
+                    "alt-speed-time-begin": 540,
+                    "alt-speed-time-day": 127,
+                    "alt-speed-time-enabled": false,
+                    "alt-speed-time-end": 1020,
+                    "alt-speed-up": 50,
+                    "blocklist-enabled": false,
+                    "blocklist-size": 0,
+                    "blocklist-url": "http://www.example.com/blocklist",
+                    "cache-size-mb": 4,
+                    "config-dir": "/config",
+                    "default-trackers": "",
+                    "dht-enabled": true,
+                    "download-dir": "/downloads/complete",
+                    "download-dir-free-space": 39943815168,
+                    "download-queue-enabled": true,
+                    "download-queue-size": 5,
+                    "encryption": "preferred",
+                    "idle-seeding-limit": 30,
+                    "idle-seeding-limit-enabled": false,
+                    "incomplete-dir": "/downloads/incomplete",
+                    "incomplete-dir-enabled": true,
+                    "lpd-enabled": false,
+                    "peer-limit-global": 200,
+                    "peer-limit-per-torrent": 50,
+                    "peer-port": 51413,
+                    "peer-port-random-on-start": false,
+                    "pex-enabled": true,
+                    "port-forwarding-enabled": false,
+                    "queue-stalled-enabled": true,
+                    "queue-stalled-minutes": 30,
+                    "rename-partial-files": true,
+                    "rpc-version": 17,
+                    "rpc-version-minimum": 14,
+                    "rpc-version-semver": "5.3.0",
+                    "script-torrent-added-enabled": false,
+                    "script-torrent-added-filename": "",
+                    "script-torrent-done-enabled": false,
+                    "script-torrent-done-filename": "",
+                    "script-torrent-done-seeding-enabled": false,
+                    "script-torrent-done-seeding-filename": "",
+                    "seed-queue-enabled": false,
+                    "seed-queue-size": 10,
+                    "seedRatioLimit": 2,
+                    "seedRatioLimited": false,
+                    "session-id": "Fk3bcmVHmSqzJ9K2qFMBq4bLX5GCPbkQ2IGYVD4ZDsNSDsIP",
+                    "speed-limit-down": 100,
+                    "speed-limit-down-enabled": false,
+                    "speed-limit-up": 100,
+                    "speed-limit-up-enabled": false,
+                    "start-added-torrents": true,
+                    "trash-original-torrent-files": false,
+                    "units": {
+                        "memory-bytes": 1024,
+                        "memory-units": ["KiB", "MiB", "GiB", "TiB"],
+                        "size-bytes": 1000,
+                        "size-units": ["kB", "MB", "GB", "TB"],
+                        "speed-bytes": 1000,
+                        "speed-units": ["kB/s", "MB/s", "GB/s", "TB/s"]
+                    },
+                    "utp-enabled": true,
+                    "version": "4.0.6 (38c164933e)"
+                },
+                "result": "success"
+            }
+            "#,
+        )?;
+        assert!(v.is_ok());
+        let session = v.arguments;
+        assert_eq!(session.alt_speed_time_day, Some(127));
+        assert_eq!(session.config_dir.as_deref(), Some("/config"));
+        assert_eq!(session.download_dir_free_space, Some(39943815168));
+        assert_eq!(session.rpc_version_semver.as_deref(), Some("5.3.0"));
+        assert_eq!(session.seed_ratio_limit, Some(2.0));
+        assert_eq!(session.tcp_enabled, None);
+        let units = session.units.expect("units should be present");
+        assert_eq!(units.memory_bytes, 1024);
+        assert_eq!(units.speed_units[0], "kB/s");
+        Ok(())
+    }
+
+    #[test]
+    fn test_session_get_selected_fields() -> Result<()> {
+        let v: RpcResponse<SessionGet> = serde_json::from_str(
+            r#"
+            {
+                "arguments": { "version": "4.0.6 (38c164933e)" },
+                "result": "success"
+            }
+            "#,
+        )?;
+        assert_eq!(v.arguments.version.as_deref(), Some("4.0.6 (38c164933e)"));
+        assert_eq!(v.arguments.download_dir, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_get_success() -> Result<()> {
+        let v: RpcResponse<BandwidthGroups> = serde_json::from_str(
+            r#"
+            {
+                "arguments": {
+                    "group": [
+                        {
+                            "honorsSessionLimits": true,
+                            "name": "tenant-a",
+                            "speed-limit-down-enabled": true,
+                            "speed-limit-down": 1024,
+                            "speed-limit-up-enabled": false,
+                            "speed-limit-up": 0
+                        }
+                    ]
+                },
+                "result": "success"
+            }
+            "#,
+        )?;
+        assert!(v.is_ok());
+        assert_eq!(v.arguments.group.len(), 1);
+        let group = &v.arguments.group[0];
+        assert!(group.honors_session_limits);
+        assert_eq!(group.name, "tenant-a");
+        assert!(group.speed_limit_down_enabled);
+        assert_eq!(group.speed_limit_down, 1024);
+        assert!(!group.speed_limit_up_enabled);
+        assert_eq!(group.speed_limit_up, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_torrent_get_recently_active() -> Result<()> {
+        let v: RpcResponse<Torrents<Torrent>> = serde_json::from_str(
+            r#"
+            {
+                "arguments": {
+                    "removed": [3, 5],
+                    "torrents": [{ "id": 1, "status": 4 }]
+                },
+                "result": "success"
+            }
+            "#,
+        )?;
+        assert_eq!(v.arguments.torrents.len(), 1);
+        assert_eq!(v.arguments.removed, Some(vec![3, 5]));
+
+        let v: RpcResponse<Torrents<Torrent>> =
+            serde_json::from_str(r#"{ "arguments": { "torrents": [] }, "result": "success" }"#)?;
+        assert_eq!(v.arguments.removed, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_torrent_get_table() -> Result<()> {
+        let v: RpcResponse<TorrentTable<Torrent>> = serde_json::from_str(
+            r#"
+            {
+                "arguments": {
+                    "torrents": [
+                        ["id", "name", "file-count", "wanted"],
+                        [1, "debian.iso", 1, [1]],
+                        [2, "ubuntu.iso", 2, [true, false]]
+                    ],
+                    "removed": [3]
+                },
+                "result": "success"
+            }
+            "#,
+        )?;
+        let torrents = Torrents::from(v.arguments);
+        assert_eq!(torrents.torrents.len(), 2);
+        assert_eq!(torrents.torrents[0].id, Some(1));
+        assert_eq!(torrents.torrents[0].name.as_deref(), Some("debian.iso"));
+        assert_eq!(torrents.torrents[1].file_count, Some(2));
+        assert_eq!(torrents.torrents[1].wanted, Some(vec![true, false]));
+        assert_eq!(torrents.removed, Some(vec![3]));
+
+        let v: RpcResponse<TorrentTable<Torrent>> =
+            serde_json::from_str(r#"{ "arguments": { "torrents": [] }, "result": "success" }"#)?;
+        assert!(v.arguments.torrents.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_torrent_get_table_short_row() {
+        let v = serde_json::from_str::<RpcResponse<TorrentTable<Torrent>>>(
+            r#"{ "arguments": { "torrents": [["id", "name"], [1]] }, "result": "success" }"#,
+        );
+        assert!(v.is_err());
+    }
+
     fn torrent_added_success() -> &'static str {
         r#"
         {
@@ -3808,3 +3808,14 @@ fn test_torrent_get_webseeds_sending_to_us_missing() -> Result<()> {
         }),
     )
 }
+
+#[test]
+fn basic_auth_debug_redacts_password() {
+    let auth = crate::types::BasicAuth {
+        user: "transmission".to_string(),
+        password: "hunter2".to_string(),
+    };
+    let debug = format!("{auth:?}");
+    assert!(debug.contains("transmission"));
+    assert!(!debug.contains("hunter2"));
+}
@@ -0,0 +1,282 @@
+// SPDX-FileCopyrightText: Copyright (c) 2020 J0rsa and contributors
+// SPDX-License-Identifier: MIT
+
+//! Torrent change events, see [`TransClient::watch`](crate::TransClient::watch).
+
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+
+use crate::{
+    types::{ErrorType, Torrent, TorrentGetField, TorrentStatus},
+    Result,
+};
+
+/// Fields [`Watcher`] needs to detect changes, always requested on top of
+/// [`WatchOptions::fields`].
+const WATCHED_FIELDS: [TorrentGetField; 5] = [
+    TorrentGetField::Id,
+    TorrentGetField::Status,
+    TorrentGetField::PercentDone,
+    TorrentGetField::Error,
+    TorrentGetField::ErrorString,
+];
+
+/// Configures [`TransClient::watch`](crate::TransClient::watch).
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// Delay between two polls of the daemon.
+    ///
+    /// The daemon only reports torrents that changed within the last minute as recently
+    /// active, so changes may be missed if this is longer than that.
+    pub interval: Duration,
+    /// Extra fields to fill in the [`Torrent`]s carried by the events. The id, status,
+    /// progress and error fields are always requested.
+    pub fields: Vec<TorrentGetField>,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        WatchOptions {
+            interval: Duration::from_secs(5),
+            fields: vec![TorrentGetField::Name, TorrentGetField::HashString],
+        }
+    }
+}
+
+impl WatchOptions {
+    pub(crate) fn request_fields(&self) -> Vec<TorrentGetField> {
+        let mut fields = WATCHED_FIELDS.to_vec();
+        for field in &self.fields {
+            if !fields.contains(field) {
+                fields.push(*field);
+            }
+        }
+        fields
+    }
+}
+
+/// A change of a torrent observed by [`TransClient::watch`](crate::TransClient::watch).
+#[derive(Debug, Clone)]
+pub enum TorrentEvent {
+    /// A torrent appeared. Emitted for every existing torrent on the first poll.
+    Added(Torrent),
+    /// The torrent with this id was removed.
+    Removed(i64),
+    StatusChanged {
+        torrent: Torrent,
+        from: TorrentStatus,
+        to: TorrentStatus,
+    },
+    /// The torrent finished downloading.
+    Completed(Torrent),
+    /// The error state changed, see [`Torrent::error_string`] for the new message.
+    ErrorChanged {
+        torrent: Torrent,
+        from: ErrorType,
+        to: ErrorType,
+    },
+    ProgressUpdated {
+        torrent: Torrent,
+        from: f32,
+        to: f32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Snapshot {
+    status: Option<TorrentStatus>,
+    percent_done: Option<f32>,
+    error: Option<ErrorType>,
+    error_string: Option<String>,
+}
+
+impl From<&Torrent> for Snapshot {
+    fn from(torrent: &Torrent) -> Self {
+        Snapshot {
+            status: torrent.status,
+            percent_done: torrent.percent_done,
+            error: torrent.error,
+            error_string: torrent.error_string.clone(),
+        }
+    }
+}
+
+/// Turns successive torrent-get responses into [`TorrentEvent`]s.
+#[derive(Debug, Default)]
+pub(crate) struct Watcher {
+    known: HashMap<i64, Snapshot>,
+    pending: VecDeque<Result<TorrentEvent>>,
+    started: bool,
+    polled: bool,
+}
+
+impl Watcher {
+    /// Returns whether the initial, full, poll succeeded.
+    pub fn started(&self) -> bool {
+        self.started
+    }
+
+    /// Returns whether the daemon was already polled, successfully or not.
+    pub fn polled(&self) -> bool {
+        self.polled
+    }
+
+    pub fn next_event(&mut self) -> Option<Result<TorrentEvent>> {
+        self.pending.pop_front()
+    }
+
+    pub fn push_error(&mut self, error: crate::Error) {
+        self.polled = true;
+        self.pending.push_back(Err(error));
+    }
+
+    /// Records a poll result and queues the events it implies.
+    pub fn update(&mut self, torrents: Vec<Torrent>, removed: Vec<i64>) {
+        self.started = true;
+        self.polled = true;
+        for torrent in torrents {
+            let Some(id) = torrent.id else {
+                continue;
+            };
+            let current = Snapshot::from(&torrent);
+            let Some(previous) = self.known.insert(id, current.clone()) else {
+                self.pending.push_back(Ok(TorrentEvent::Added(torrent)));
+                continue;
+            };
+            if previous == current {
+                continue;
+            }
+            if let (Some(from), Some(to)) = (previous.error, current.error) {
+                if from != to || previous.error_string != current.error_string {
+                    self.pending.push_back(Ok(TorrentEvent::ErrorChanged {
+                        torrent: torrent.clone(),
+                        from,
+                        to,
+                    }));
+                }
+            }
+            if let (Some(from), Some(to)) = (previous.status, current.status) {
+                if from != to {
+                    self.pending.push_back(Ok(TorrentEvent::StatusChanged {
+                        torrent: torrent.clone(),
+                        from,
+                        to,
+                    }));
+                }
+            }
+            if let (Some(from), Some(to)) = (previous.percent_done, current.percent_done) {
+                if from != to {
+                    let completed = from < 1.0 && to >= 1.0;
+                    self.pending.push_back(Ok(TorrentEvent::ProgressUpdated {
+                        torrent: torrent.clone(),
+                        from,
+                        to,
+                    }));
+                    if completed {
+                        self.pending.push_back(Ok(TorrentEvent::Completed(torrent)));
+                    }
+                }
+            }
+        }
+        for id in removed {
+            if self.known.remove(&id).is_some() {
+                self.pending.push_back(Ok(TorrentEvent::Removed(id)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{TorrentEvent, Watcher};
+    use crate::types::{Torrent, TorrentStatus};
+
+    fn torrent(id: i64, status: u8, percent_done: f32, error: u8) -> Torrent {
+        serde_json::from_value(json!({
+            "id": id,
+            "status": status,
+            "percentDone": percent_done,
+            "error": error,
+            "errorString": "",
+        }))
+        .unwrap()
+    }
+
+    fn events(watcher: &mut Watcher) -> Vec<TorrentEvent> {
+        std::iter::from_fn(|| watcher.next_event())
+            .map(|event| event.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn initial_poll_adds_everything() {
+        let mut watcher = Watcher::default();
+        assert!(!watcher.started());
+        watcher.update(vec![torrent(1, 4, 0.5, 0), torrent(2, 6, 1.0, 0)], vec![]);
+        assert!(watcher.started());
+        let events = events(&mut watcher);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], TorrentEvent::Added(t) if t.id == Some(1)));
+        assert!(matches!(&events[1], TorrentEvent::Added(t) if t.id == Some(2)));
+    }
+
+    #[test]
+    fn unchanged_torrent_is_silent() {
+        let mut watcher = Watcher::default();
+        watcher.update(vec![torrent(1, 4, 0.5, 0)], vec![]);
+        events(&mut watcher);
+        watcher.update(vec![torrent(1, 4, 0.5, 0)], vec![]);
+        assert!(events(&mut watcher).is_empty());
+    }
+
+    #[test]
+    fn completion() {
+        let mut watcher = Watcher::default();
+        watcher.update(vec![torrent(1, 4, 0.9, 0)], vec![]);
+        events(&mut watcher);
+        watcher.update(vec![torrent(1, 6, 1.0, 0)], vec![]);
+        let events = events(&mut watcher);
+        assert_eq!(events.len(), 3);
+        assert!(matches!(
+            &events[0],
+            TorrentEvent::StatusChanged {
+                from: TorrentStatus::Downloading,
+                to: TorrentStatus::Seeding,
+                ..
+            }
+        ));
+        assert!(matches!(
+            &events[1],
+            TorrentEvent::ProgressUpdated { from, to, .. } if *from == 0.9 && *to == 1.0
+        ));
+        assert!(matches!(&events[2], TorrentEvent::Completed(t) if t.id == Some(1)));
+    }
+
+    #[test]
+    fn error_changed() {
+        let mut watcher = Watcher::default();
+        watcher.update(vec![torrent(1, 4, 0.5, 0)], vec![]);
+        events(&mut watcher);
+        watcher.update(vec![torrent(1, 4, 0.5, 2)], vec![]);
+        let events = events(&mut watcher);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(&events[0], TorrentEvent::ErrorChanged { .. }));
+    }
+
+    #[test]
+    fn removed_only_once() {
+        let mut watcher = Watcher::default();
+        watcher.update(vec![torrent(1, 4, 0.5, 0)], vec![]);
+        events(&mut watcher);
+        watcher.update(vec![], vec![1, 7]);
+        let events_ = events(&mut watcher);
+        assert_eq!(events_.len(), 1);
+        assert!(matches!(events_[0], TorrentEvent::Removed(1)));
+        watcher.update(vec![], vec![1]);
+        assert!(events(&mut watcher).is_empty());
+    }
+}